@@ -0,0 +1,50 @@
+//! Per-pixel cost of blending, before and after the switch to rounded integer math.
+//!
+//! Run with `cargo bench --bench blend`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rtfractal::pixel::PixelGrid;
+
+const SIZE: u32 = 512;
+const PASSES: u32 = 20;
+
+/// The float blend `PixelGrid::blend_pixel` used to do, truncating, pixel by pixel like it.
+fn blend_float(buffer: &mut [u8], pixel: &[u8; 3], alpha: f32) {
+	for y in 0..SIZE as usize {
+		for x in 0..SIZE as usize {
+			let i = y * SIZE as usize * 4 + x * 4;
+			for (dst, src) in buffer[i..i + 3].iter_mut().zip(pixel) {
+				*dst = ((*dst as f32 / 255.0 * (1.0 - alpha) + *src as f32 / 255.0 * alpha) * 255.0) as u8;
+			}
+		}
+	}
+}
+
+fn blend_integer(buffer: &mut [u8], pixel: &[u8; 3], alpha: f32) {
+	let mut grid = PixelGrid::new(buffer, SIZE, SIZE, SIZE as usize * 4);
+	for y in 0..SIZE {
+		for x in 0..SIZE {
+			grid.blend_pixel(x, y, pixel, alpha);
+		}
+	}
+}
+
+/// Nanoseconds per pixel of `blend` over the whole buffer, the best of `PASSES` runs.
+fn time(blend: fn(&mut [u8], &[u8; 3], f32)) -> f64 {
+	let mut buffer = vec![0x80; SIZE as usize * SIZE as usize * 4];
+	(0..PASSES).map(|pass| {
+		let start = Instant::now();
+		blend(black_box(&mut buffer), &[0x23, 0xA9, 0x50], black_box(0.1 + pass as f32 * 0.04));
+		start.elapsed()
+	}).min().unwrap_or(Duration::ZERO).as_secs_f64() * 1e9 / (SIZE * SIZE) as f64
+}
+
+fn main() {
+	let before = time(blend_float);
+	let after = time(blend_integer);
+	println!("float, truncating:  {:.2} ns per pixel", before);
+	println!("integer, rounding:  {:.2} ns per pixel", after);
+	println!("ratio:              {:.2}", after / before);
+}
@@ -0,0 +1,195 @@
+use std::path::PathBuf;
+
+/// Canvas size used when `--size` isn't given.
+const DEFAULT_SIZE: (u32, u32) = (1000, 1000);
+/// Feedback generations `render` runs when `--iterations` isn't given.
+const DEFAULT_RENDER_ITERATIONS: u32 = 12;
+/// Most jittered renders `render --samples` averages, already well past what 8-bit channels can show.
+const MAX_SAMPLES: usize = 1024;
+
+/// What the program was asked to do.
+pub enum Command {
+	/// Open the window and edit interactively.
+	Interactive(Args),
+	/// Render a saved composition straight to a PNG, without a window.
+	Render(RenderArgs),
+}
+
+impl Command {
+	/// Parse the arguments after the program name; a leading `render` selects the headless
+	/// renderer, anything else is the interactive app.
+	pub fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+		let mut args = args.peekable();
+		if args.peek().map(String::as_str) == Some("render") {
+			args.next();
+			RenderArgs::parse(args).map(Command::Render)
+		} else {
+			Args::parse(args).map(Command::Interactive)
+		}
+	}
+}
+
+/// Command line options for the interactive app.
+pub struct Args {
+	/// Canvas width and height in pixels.
+	pub size: (u32, u32),
+	/// Image to resume the feedback loop from instead of starting at the seed.
+	pub continue_from: Option<PathBuf>,
+	/// Composition to load at startup.
+	pub load: Option<PathBuf>,
+	/// Image to use as the seed instead of the built-in triangle.
+	pub seed: Option<PathBuf>,
+	/// Resize the canvas along with the window instead of stretching it.
+	pub fit_window: bool,
+	/// Seed of the generator X rolls transform colors from.
+	pub color_seed: Option<u64>,
+}
+
+impl Default for Args {
+	fn default() -> Self {
+		Self {
+			size: DEFAULT_SIZE,
+			continue_from: None,
+			load: None,
+			seed: None,
+			fit_window: false,
+			color_seed: None,
+		}
+	}
+}
+
+impl Args {
+	pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+		let mut parsed = Self::default();
+		while let Some(arg) = args.next() {
+			match arg.as_str() {
+				"--continue" => {
+					let path = args.next().ok_or("--continue expects an image path")?;
+					parsed.continue_from = Some(PathBuf::from(path));
+				},
+				"--load" => {
+					let path = args.next().ok_or("--load expects a composition path")?;
+					parsed.load = Some(PathBuf::from(path));
+				},
+				"--size" => {
+					let size = args.next().ok_or("--size expects WIDTHxHEIGHT")?;
+					parsed.size = parse_size(&size)?;
+				},
+				"--fit-window" => parsed.fit_window = true,
+				"--color-seed" => {
+					let seed = args.next().ok_or("--color-seed expects a number")?;
+					parsed.color_seed = Some(seed.parse().map_err(|_| format!("invalid color seed `{}`", seed))?);
+				},
+				"--seed" => {
+					let path = args.next().ok_or("--seed expects an image path")?;
+					parsed.seed = Some(PathBuf::from(path));
+				},
+				_ => return Err(format!("unrecognized argument `{}`", arg)),
+			}
+		}
+		Ok(parsed)
+	}
+}
+
+/// Parse a `WIDTHxHEIGHT` canvas size such as `1920x1080`.
+fn parse_size(size: &str) -> Result<(u32, u32), String> {
+	let invalid = || format!("invalid size `{}`, expected WIDTHxHEIGHT", size);
+	let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+	let width: u32 = width.parse().map_err(|_| invalid())?;
+	let height: u32 = height.parse().map_err(|_| invalid())?;
+	if width == 0 || height == 0 { return Err(invalid()) }
+	// Keep the RGBA buffer length representable on every target, not just 64-bit ones
+	if width.checked_mul(height).and_then(|pixels| pixels.checked_mul(4)).is_none() {
+		return Err(format!("size `{}` is too large", size));
+	}
+	Ok((width, height))
+}
+
+/// Options for `rtfractal render`.
+pub struct RenderArgs {
+	/// Composition to render, or `-` to read it from stdin.
+	pub input: PathBuf,
+	/// Where to write the PNG.
+	pub out: PathBuf,
+	/// Feedback generations to run from the seed.
+	pub iterations: u32,
+	pub size: (u32, u32),
+	pub seed: Option<PathBuf>,
+	/// Jittered renders averaged into the output, for anti-aliasing; 1 is a single plain render.
+	pub samples: usize,
+}
+
+impl RenderArgs {
+	pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+		let mut input = None;
+		let mut out = None;
+		let mut iterations = DEFAULT_RENDER_ITERATIONS;
+		let mut size = DEFAULT_SIZE;
+		let mut seed = None;
+		let mut samples = 1;
+		while let Some(arg) = args.next() {
+			match arg.as_str() {
+				"--input" => input = Some(PathBuf::from(args.next().ok_or("--input expects a composition path")?)),
+				"--out" => out = Some(PathBuf::from(args.next().ok_or("--out expects an image path")?)),
+				"--iterations" => {
+					let count = args.next().ok_or("--iterations expects a count")?;
+					iterations = count.parse().map_err(|_| format!("invalid iteration count `{}`", count))?;
+				},
+				"--size" => {
+					let value = args.next().ok_or("--size expects WIDTHxHEIGHT")?;
+					size = parse_size(&value)?;
+				},
+				"--seed" => seed = Some(PathBuf::from(args.next().ok_or("--seed expects an image path")?)),
+				"--samples" => {
+					let count = args.next().ok_or("--samples expects a count")?;
+					samples = count.parse().ok().filter(|&samples| samples > 0).ok_or_else(|| format!("invalid sample count `{}`", count))?;
+					if samples > MAX_SAMPLES { return Err(format!("sample count `{}` is more than {}", count, MAX_SAMPLES)) }
+				},
+				_ => return Err(format!("unrecognized render argument `{}`", arg)),
+			}
+		}
+		Ok(Self {
+			input: input.ok_or("render needs --input")?,
+			out: out.ok_or("render needs --out")?,
+			iterations,
+			size,
+			seed,
+			samples,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_size_rejects_buffers_that_overflow() {
+		assert_eq!(parse_size("1920x1080"), Ok((1920, 1080)));
+		assert_eq!(parse_size("32768x32767"), Ok((32768, 32767)));
+		assert!(parse_size("32768x32768").is_err());
+		assert!(parse_size("40000x40000").is_err());
+		assert!(parse_size("0x10").is_err());
+	}
+
+	#[test]
+	fn render_samples_default_to_one_and_stay_in_range() {
+		let parse = |extra: &[&str]| RenderArgs::parse(["--input", "a.json", "--out", "a.png"].iter().chain(extra).map(|s| s.to_string()));
+		assert_eq!(parse(&[]).unwrap().samples, 1);
+		assert_eq!(parse(&["--samples", "8"]).unwrap().samples, 8);
+		assert!(parse(&["--samples", "0"]).is_err());
+		assert_eq!(parse(&["--samples", "1024"]).unwrap().samples, MAX_SAMPLES);
+		assert!(parse(&["--samples", "1025"]).is_err());
+		// Would truncate to zero as a u32
+		assert!(parse(&["--samples", "4294967296"]).is_err());
+		assert!(parse(&["--samples"]).is_err());
+	}
+
+	#[test]
+	fn color_seed_is_a_number() {
+		let parse = |args: &[&str]| Args::parse(args.iter().map(|s| s.to_string()));
+		assert_eq!(parse(&[]).unwrap().color_seed, None);
+		assert_eq!(parse(&["--color-seed", "42"]).unwrap().color_seed, Some(42));
+		assert!(parse(&["--color-seed", "red"]).is_err());
+	}
+}
@@ -0,0 +1,192 @@
+//! A C interface to `FractalWorld`, for embedding the renderer from other languages.
+//!
+//! A context is created with `rtfractal_create` and owned by the caller until it is passed to
+//! `rtfractal_destroy`. Frames are copied into buffers the caller allocates, so no memory
+//! crosses the boundary in either direction.
+//!
+//! ```c
+//! RtfractalContext *context = rtfractal_create(256, 256);
+//! rtfractal_add_transform(context, 0.5, 0.0, 0.0, 0.5, 0.0, 64.0, 255);
+//! rtfractal_add_transform(context, 0.5, 0.0, 0.0, 0.5, 128.0, 64.0, 255);
+//! rtfractal_step(context, 8);
+//! uint8_t frame[256 * 256 * 4];
+//! rtfractal_copy_frame(context, frame, sizeof frame);
+//! rtfractal_destroy(context);
+//! ```
+
+#![allow(unsafe_code)]
+
+use crate::fractal::seed_triangle;
+use crate::pixel::{Transform, Vec2};
+use crate::FractalWorld;
+
+/// The seed the editor starts from: its default green triangle on the default grey.
+const SEED_COLOR: [u8; 3] = [0x23, 0xA9, 0x50];
+const BACKGROUND_COLOR: [u8; 3] = [0xE3, 0xE3, 0xE3];
+
+/// A composition and the generation it has grown to, opaque to C.
+pub struct RtfractalContext {
+	world: FractalWorld,
+	source: Vec<u8>,
+	dest: Vec<u8>,
+}
+
+/// A context for a `width`×`height` canvas growing from the editor's default seed, with no
+/// transforms yet. Null if either side is zero or the buffer size overflows.
+#[no_mangle]
+pub extern "C" fn rtfractal_create(width: u32, height: u32) -> *mut RtfractalContext {
+	let len = match (width as usize).checked_mul(height as usize).and_then(|pixels| pixels.checked_mul(4)) {
+		Some(len) if len > 0 => len,
+		_ => return std::ptr::null_mut(),
+	};
+	let world = FractalWorld::new(width, height, seed_triangle(width, height, SEED_COLOR, BACKGROUND_COLOR));
+	Box::into_raw(Box::new(RtfractalContext { world, source: vec![0; len], dest: vec![0; len] }))
+}
+
+/// Free `context`. Null is ignored.
+///
+/// # Safety
+///
+/// `context` must be null or come from `rtfractal_create`, and not be used again afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rtfractal_destroy(context: *mut RtfractalContext) {
+	if !context.is_null() {
+		drop(Box::from_raw(context));
+	}
+}
+
+/// Add a transform behind the existing ones that copies the canvas pixel at `p` to
+/// `[a b; c d] p + (e, f)` with opacity `alpha`.
+///
+/// Returns false, adding nothing, if `context` is null or the matrix isn't a rotation times an
+/// axis scale (see `Transform::from_affine`).
+///
+/// # Safety
+///
+/// `context` must be null or a live context from `rtfractal_create`.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn rtfractal_add_transform(context: *mut RtfractalContext, a: f32, b: f32, c: f32, d: f32, e: f32, f: f32, alpha: u8) -> bool {
+	let context = match context.as_mut() {
+		Some(context) => context,
+		None => return false,
+	};
+	let transform = match Transform::from_affine([a, b, c, d, e, f]) {
+		Some(transform) => transform,
+		None => return false,
+	};
+	// Feedback feeds source points in relative to the canvas center
+	let center = Vec2::new(context.world.width() as f32 / 2.0, context.world.height() as f32 / 2.0);
+	context.world.add_transform(Transform { position: transform.apply(center), alpha, ..transform });
+	true
+}
+
+/// Grow the fractal by `iterations` more generations. A new context is an empty canvas, so its
+/// first generation is just the seed.
+///
+/// # Safety
+///
+/// `context` must be null or a live context from `rtfractal_create`.
+#[no_mangle]
+pub unsafe extern "C" fn rtfractal_step(context: *mut RtfractalContext, iterations: u32) {
+	if let Some(context) = context.as_mut() {
+		for _ in 0..iterations {
+			context.world.step(&mut context.source, &mut context.dest);
+			std::mem::swap(&mut context.source, &mut context.dest);
+		}
+	}
+}
+
+/// Copy the current generation into `out` as opaque RGBA rows, top first.
+///
+/// Returns false, writing nothing, if `context` or `out` is null or `len` is less than
+/// width × height × 4 bytes.
+///
+/// # Safety
+///
+/// `context` must be null or a live context from `rtfractal_create`, and `out` must be null or
+/// valid for writing `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rtfractal_copy_frame(context: *const RtfractalContext, out: *mut u8, len: usize) -> bool {
+	let context = match context.as_ref() {
+		Some(context) => context,
+		None => return false,
+	};
+	if out.is_null() || len < context.source.len() { return false }
+	let out = std::slice::from_raw_parts_mut(out, context.source.len());
+	out.copy_from_slice(&context.source);
+	// The alpha channel is only the feedback mask
+	for pixel in out.chunks_exact_mut(4) {
+		pixel[3] = 0xff;
+	}
+	true
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SIZE: u32 = 128;
+
+	/// Two side-by-side halves and a turned, squashed, flipped third, as the editor defines them.
+	fn native_transforms() -> [Transform; 3] {
+		let half = |x| Transform { position: Vec2::new(x, 64.0), scale: Vec2::new(0.5, 0.5), ..Transform::identity() };
+		let turned = Transform { position: Vec2::new(60.0, 80.0), rotation: -0.6, scale: Vec2::new(0.4, -0.3), alpha: 0x90, ..Transform::identity() };
+		[half(32.0), half(96.0), turned]
+	}
+
+	/// The same transforms as canvas-to-canvas matrices, through the C API.
+	fn render(steps: &[u32]) -> Vec<u8> {
+		let mut frame = vec![0; SIZE as usize * SIZE as usize * 4];
+		unsafe {
+			let context = rtfractal_create(SIZE, SIZE);
+			for transform in native_transforms() {
+				// p goes to R S (p - center) + position
+				let (sin, cos) = transform.rotation.sin_cos();
+				let (sx, sy) = (transform.scale.x, transform.scale.y);
+				let (a, b, c, d) = (sx * cos, -sy * sin, sx * sin, sy * cos);
+				let center = SIZE as f32 / 2.0;
+				let (e, f) = (transform.position.x - (a + b) * center, transform.position.y - (c + d) * center);
+				assert!(rtfractal_add_transform(context, a, b, c, d, e, f, transform.alpha));
+			}
+			for &iterations in steps {
+				rtfractal_step(context, iterations);
+			}
+			assert!(rtfractal_copy_frame(context, frame.as_mut_ptr(), frame.len()));
+			rtfractal_destroy(context);
+		}
+		frame
+	}
+
+	#[test]
+	fn c_api_matches_the_native_path() {
+		let mut world = FractalWorld::new(SIZE, SIZE, seed_triangle(SIZE, SIZE, SEED_COLOR, BACKGROUND_COLOR));
+		for transform in native_transforms() {
+			world.add_transform(transform);
+		}
+		let mut native = vec![0; SIZE as usize * SIZE as usize * 4];
+		world.render_into(&mut native, 6);
+		assert!(render(&[6]) == native);
+		// Stepping continues where the last call stopped
+		assert!(render(&[2, 1, 3]) == render(&[6]));
+	}
+
+	#[test]
+	fn c_api_rejects_what_it_cannot_render() {
+		assert!(rtfractal_create(0, 16).is_null());
+		assert!(rtfractal_create(u32::MAX, u32::MAX).is_null());
+		unsafe {
+			let context = rtfractal_create(16, 16);
+			// A shear
+			assert!(!rtfractal_add_transform(context, 0.5, 0.3, 0.0, 0.5, 0.0, 0.0, 0xff));
+			assert!(!rtfractal_add_transform(std::ptr::null_mut(), 0.5, 0.0, 0.0, 0.5, 0.0, 0.0, 0xff));
+			let mut frame = [0u8; 16 * 16 * 4];
+			assert!(!rtfractal_copy_frame(context, frame.as_mut_ptr(), frame.len() - 1));
+			assert!(!rtfractal_copy_frame(context, std::ptr::null_mut(), frame.len()));
+			assert!(rtfractal_copy_frame(context, frame.as_mut_ptr(), frame.len()));
+			rtfractal_step(std::ptr::null_mut(), 1);
+			rtfractal_destroy(context);
+			rtfractal_destroy(std::ptr::null_mut());
+		}
+	}
+}
@@ -0,0 +1,107 @@
+use rand::distributions::{Distribution, WeightedIndex};
+
+use crate::pixel::{Transform, Vec2};
+
+/// Orbit steps thrown away after a restart, while the point falls onto the attractor.
+const BURN_IN: usize = 20;
+
+/// Hit counts per canvas pixel.
+pub struct AccumulationGrid {
+	counts: Vec<u32>,
+	width: u32,
+	height: u32,
+	max: u32,
+}
+
+impl AccumulationGrid {
+	pub fn new(width: u32, height: u32) -> Self {
+		Self {
+			counts: vec![0; width as usize * height as usize],
+			width,
+			height,
+			max: 0,
+		}
+	}
+
+	pub fn clear(&mut self) {
+		self.counts.fill(0);
+		self.max = 0;
+	}
+
+	/// Count a hit on the pixel containing `point`, returning whether it's the first one there.
+	/// Points off the grid are dropped.
+	pub fn plot(&mut self, point: Vec2) -> bool {
+		if !(point.x >= 0.0 && point.x < self.width as f32 && point.y >= 0.0 && point.y < self.height as f32) { return false }
+		let count = &mut self.counts[point.y as usize * self.width as usize + point.x as usize];
+		*count = count.saturating_add(1);
+		self.max = self.max.max(*count);
+		*count == 1
+	}
+
+	/// Write the grid into an RGBA `frame` of the same size, shading each pixel from `background`
+	/// towards `ink` by the log of its hit count so sparse and dense regions both show.
+	pub fn tonemap(&self, frame: &mut [u8], background: [u8; 3], ink: [u8; 3]) {
+		let scale = 1.0 / (1.0 + self.max as f32).ln().max(f32::EPSILON);
+		for (pixel, &count) in frame.chunks_exact_mut(4).zip(&self.counts) {
+			let brightness = (1.0 + count as f32).ln() * scale;
+			for c in 0..3 {
+				pixel[c] = (background[c] as f32 + (ink[c] as f32 - background[c] as f32) * brightness).round() as u8;
+			}
+			pixel[3] = 0xff;
+		}
+	}
+}
+
+/// The chaos game: a point repeatedly moved by a randomly picked transform, with every position
+/// it visits accumulated into a grid that refines across frames.
+pub struct ChaosGame {
+	grid: AccumulationGrid,
+	point: Option<Vec2>,
+}
+
+impl ChaosGame {
+	pub fn new(width: u32, height: u32) -> Self {
+		Self {
+			grid: AccumulationGrid::new(width, height),
+			point: None,
+		}
+	}
+
+	/// Forget the accumulated image, e.g. after the transforms changed.
+	pub fn reset(&mut self) {
+		self.grid.clear();
+		self.point = None;
+	}
+
+	pub fn grid(&self) -> &AccumulationGrid {
+		&self.grid
+	}
+
+	/// Advance the orbit `steps` times, returning how many pixels were hit for the first time.
+	///
+	/// Transforms are picked with probability proportional to their area times their weight, so
+	/// each part of the attractor gets a density matching its size. With no transforms, or only
+	/// zero-weight ones, nothing is plotted.
+	pub fn run(&mut self, transforms: &[&Transform], steps: usize) -> usize {
+		let weights = transforms.iter().map(|t| (t.scale.x * t.scale.y).abs() * t.weight);
+		let choice = match WeightedIndex::new(weights) {
+			Ok(choice) => choice,
+			Err(_) => return 0,
+		};
+		// Each transform maps canvas points relative to the canvas center, as in the feedback
+		let center = Vec2::new(self.grid.width as f32 / 2.0, self.grid.height as f32 / 2.0);
+		let mut rng = rand::thread_rng();
+		let (mut point, burn_in) = match self.point {
+			Some(point) => (point, 0),
+			None => (center, BURN_IN),
+		};
+		let mut newly_hit = 0;
+		for step in 0..burn_in + steps {
+			point = transforms[choice.sample(&mut rng)].apply(point - center);
+			if !(point.x.is_finite() && point.y.is_finite()) { point = center; }
+			if step >= burn_in && self.grid.plot(point) { newly_hit += 1; }
+		}
+		self.point = Some(point);
+		newly_hit
+	}
+}
@@ -0,0 +1,507 @@
+//! The feedback fractal without any windowing: transforms, a seed and the compositing between
+//! generations.
+
+use rand::Rng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::pixel::{PixelGrid, Transform, Vec2};
+
+/// How feedback reads the previous generation.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum Sampling {
+	/// Take the single source pixel, for crisp pixel-art edges.
+	Nearest,
+	/// Interpolate bilinearly between the four nearest source pixels, for smooth edges.
+	Smooth,
+}
+
+/// Composition-wide mirror folds applied to every feedback generation.
+#[derive(Default, Copy, Clone, Debug)]
+pub struct Symmetry {
+	/// Mirror left and right across the center column.
+	pub vertical: bool,
+	/// Mirror top and bottom across the center row.
+	pub horizontal: bool,
+}
+
+/// The settings one feedback generation is composited with.
+#[derive(Copy, Clone, Debug)]
+pub struct Feedback {
+	/// Canvas size in pixels; every buffer is RGBA at this size.
+	pub width: u32,
+	pub height: u32,
+	pub sampling: Sampling,
+	pub symmetry: Symmetry,
+}
+
+impl Feedback {
+	/// Run one feedback generation: `seed` with each of `transforms`' copies of `source`
+	/// composited over it, written to `dest`.
+	///
+	/// `transforms` are front to back, so the first is composited last and ends up on top; those
+	/// with zero weight are skipped. The alpha channel masks what gets copied: fully transparent
+	/// pixels are left out.
+	pub fn step(&self, transforms: &[&Transform], seed: &[u8], source: &mut [u8], dest: &mut [u8]) {
+		let (width, height) = (self.width, self.height);
+		dest.copy_from_slice(seed);
+		// Gather: every destination pixel pulls from each transform's preimage in the last
+		// generation, so rows are independent and transforms always blend back to front
+		let source = PixelGrid::new(source, width, height, width as usize * 4);
+		let center = Vec2::new(width as f32 / 2.0, height as f32 / 2.0);
+		// Each copy only lands inside its transformed canvas, so skip pixels outside its bounds
+		let transforms: Vec<(&Transform, Vec2, Vec2)> = transforms.iter()
+			.rev()
+			.filter(|transform| transform.weight > 0.0)
+			.map(|&transform| {
+				let (min, max) = transform.canvas_bounds(width, height);
+				(transform, min, max)
+			})
+			.collect();
+		let sampling = self.sampling;
+		let composite_row = |(y, row): (usize, &mut [u8])| {
+			let mut row = PixelGrid::new(row, width, 1, width as usize * 4);
+			for x in 0..width {
+				let point = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+				for &(transform, min, max) in &transforms {
+					if point.x < min.x || point.x > max.x || point.y < min.y || point.y > max.y { continue }
+					// Feedback feeds canvas points in relative to the canvas center
+					let source_point = transform.apply_inverse(point) + center;
+					let color = match sampling {
+						Sampling::Nearest => source.sample(source_point),
+						Sampling::Smooth => source.sample_bilinear(source_point),
+					};
+					let color = match color {
+						Some(color) if color[3] > 0 => color,
+						_ => continue,
+					};
+					let tinted = transform.tinted([color[0], color[1], color[2]]);
+					row.composite_pixel(x, 0, &tinted, transform.copy_alpha() * transform.weight * color[3] as f32 / 255.0);
+				}
+			}
+		};
+		#[cfg(feature = "parallel")]
+		dest.par_chunks_mut(width as usize * 4).enumerate().for_each(composite_row);
+		#[cfg(not(feature = "parallel"))]
+		dest.chunks_mut(width as usize * 4).enumerate().for_each(composite_row);
+		let mut grid = PixelGrid::new(dest, width, height, width as usize * 4);
+		if self.symmetry.vertical { grid.fold_vertical(); }
+		if self.symmetry.horizontal { grid.fold_horizontal(); }
+	}
+}
+
+/// A composition and the seed it grows from, ready to render offscreen.
+///
+/// ```
+/// use rtfractal::fractal::{seed_triangle, FractalWorld};
+/// use rtfractal::pixel::{Transform, Vec2};
+///
+/// let (width, height) = (256, 256);
+/// let background = [0xE3, 0xE3, 0xE3];
+/// let mut world = FractalWorld::new(width, height, seed_triangle(width, height, [0x23, 0xA9, 0x50], background));
+/// for x in [64.0, 192.0] {
+///     world.add_transform(Transform {
+///         position: Vec2::new(x, 128.0),
+///         scale: Vec2::new(0.5, 0.5),
+///         ..Transform::identity()
+///     });
+/// }
+/// let mut frame = vec![0; width as usize * height as usize * 4];
+/// world.render_into(&mut frame, 4);
+/// assert!(frame.chunks_exact(4).any(|pixel| pixel[..3] != background));
+/// ```
+pub struct FractalWorld {
+	/// Front to back, as for `Feedback::step`.
+	transforms: Vec<Transform>,
+	pub sampling: Sampling,
+	pub symmetry: Symmetry,
+	width: u32,
+	height: u32,
+	seed: Vec<u8>,
+}
+
+impl FractalWorld {
+	/// An empty composition on a `width`×`height` canvas, growing from `seed`, an RGBA buffer of
+	/// that size whose alpha channel marks the pixels to copy.
+	pub fn new(width: u32, height: u32, seed: Vec<u8>) -> Self {
+		assert_eq!(seed.len(), width as usize * height as usize * 4, "seed doesn't match the {}x{} canvas", width, height);
+		Self {
+			transforms: Vec::new(),
+			sampling: Sampling::Nearest,
+			symmetry: Symmetry::default(),
+			width,
+			height,
+			seed,
+		}
+	}
+
+	pub fn width(&self) -> u32 {
+		self.width
+	}
+
+	pub fn height(&self) -> u32 {
+		self.height
+	}
+
+	pub fn transforms(&self) -> &[Transform] {
+		&self.transforms
+	}
+
+	/// Add `transform` behind the existing ones.
+	pub fn add_transform(&mut self, transform: Transform) {
+		self.transforms.push(transform);
+	}
+
+	/// Remove and return the transform at `index`.
+	///
+	/// Panics if `index` is out of bounds.
+	pub fn remove_transform(&mut self, index: usize) -> Transform {
+		self.transforms.remove(index)
+	}
+
+	/// Run one feedback generation from `source` into `dest`; see `Feedback::step`.
+	pub fn step(&self, source: &mut [u8], dest: &mut [u8]) {
+		let transforms: Vec<&Transform> = self.transforms.iter().collect();
+		self.feedback().step(&transforms, &self.seed, source, dest);
+	}
+
+	/// Grow the fractal for `iterations` generations from an empty canvas, so one generation is
+	/// just the seed, and write it to `frame` with every pixel opaque.
+	pub fn render_into(&self, frame: &mut [u8], iterations: u32) {
+		let mut source = vec![0u8; self.seed.len()];
+		let mut dest = vec![0u8; self.seed.len()];
+		for _ in 0..iterations {
+			self.step(&mut source, &mut dest);
+			std::mem::swap(&mut source, &mut dest);
+		}
+		frame.copy_from_slice(&source);
+		// The alpha channel is only the feedback mask
+		for pixel in frame.chunks_exact_mut(4) {
+			pixel[3] = 0xff;
+		}
+	}
+
+	/// Average `samples` renders of `iterations` generations into `frame`, each with the whole
+	/// composition shifted by an offset `rng` picks inside a square `jitter` pixels wide.
+	///
+	/// Stochastic supersampling: with a `jitter` of 1 the average approaches every pixel's
+	/// coverage, smoothing aliased edges for `samples` times the work and no larger buffer.
+	pub fn render_supersampled(&self, frame: &mut [u8], iterations: u32, samples: usize, jitter: f32, rng: &mut impl Rng) {
+		let samples = samples.max(1);
+		// Wide enough that no realistic sample count overflows it
+		let mut sum = vec![0u64; frame.len()];
+		for _ in 0..samples {
+			let offset = Vec2::new(rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5)) * jitter;
+			self.shifted(offset).render_into(frame, iterations);
+			for (sum, &value) in sum.iter_mut().zip(frame.iter()) {
+				*sum += value as u64;
+			}
+		}
+		for (value, sum) in frame.iter_mut().zip(sum) {
+			*value = ((sum + samples as u64 / 2) / samples as u64) as u8;
+		}
+	}
+
+	/// This composition with the seed and every generation grown from it moved by `offset` pixels.
+	fn shifted(&self, offset: Vec2) -> FractalWorld {
+		let seed = if offset == Vec2::new(0.0, 0.0) { self.seed.clone() } else { shift_bilinear(&self.seed, self.width, self.height, offset) };
+		// Conjugating by the shift: a source point moved by `offset` lands moved by `offset` too
+		let transforms = self.transforms.iter()
+			.map(|transform| Transform { position: transform.position + offset - (transform.apply(offset) - transform.position), ..*transform })
+			.collect();
+		FractalWorld { transforms, seed, ..*self }
+	}
+
+	fn feedback(&self) -> Feedback {
+		Feedback {
+			width: self.width,
+			height: self.height,
+			sampling: self.sampling,
+			symmetry: self.symmetry,
+		}
+	}
+}
+
+/// The RGBA `buffer` moved by a fraction of a pixel, with every channel interpolated between
+/// the four pixels around where each one comes from; past the edge it repeats the edge pixels.
+///
+/// Unlike `PixelGrid::sample_bilinear` transparent pixels keep their color, since the seed's are
+/// the background that is shown.
+fn shift_bilinear(buffer: &[u8], width: u32, height: u32, offset: Vec2) -> Vec<u8> {
+	let mut shifted = vec![0u8; buffer.len()];
+	for (i, pixel) in shifted.chunks_exact_mut(4).enumerate() {
+		let x = (i % width as usize) as f32 - offset.x;
+		let y = (i / width as usize) as f32 - offset.y;
+		let (x0, y0) = (x.floor(), y.floor());
+		let (tx, ty) = (x - x0, y - y0);
+		let mut sum = [0.0f32; 4];
+		for (dx, dy, weight) in [	(0.0, 0.0, (1.0 - tx) * (1.0 - ty)),
+									(1.0, 0.0, tx * (1.0 - ty)),
+									(0.0, 1.0, (1.0 - tx) * ty),
+									(1.0, 1.0, tx * ty)] {
+			let sx = (x0 + dx).clamp(0.0, (width - 1) as f32) as usize;
+			let sy = (y0 + dy).clamp(0.0, (height - 1) as f32) as usize;
+			let j = (sy * width as usize + sx) * 4;
+			for (c, channel) in sum.iter_mut().enumerate() {
+				*channel += buffer[j + c] as f32 * weight;
+			}
+		}
+		for (value, channel) in pixel.iter_mut().zip(sum) {
+			*value = channel.round() as u8;
+		}
+	}
+	shifted
+}
+
+/// A `color` triangle in the middle of an otherwise `background` canvas.
+///
+/// The alpha channel marks which pixels feedback copies; the background is left out.
+pub fn seed_triangle(width: u32, height: u32, color: [u8; 3], background: [u8; 3]) -> Vec<u8> {
+	let mut clear_buffer = vec![0u8; width as usize * height as usize * 4];
+	for i in 0..width as usize * height as usize {
+		let x = (i % width as usize) as u32;
+		let y = (i / width as usize) as u32;
+		// if x > width * 4 / 10 && x < width * 6 / 10 &&
+			// y > height * 4 / 10 && y < height * 6 / 10 {
+		let x = x as f32 - width as f32 / 2.0;
+		let y = y as f32 - height as f32 / 2.0;
+		let scale = 200.0;
+		if 	x > -0.5 * scale && x < 0.5 * scale &&
+			y < 0.0 * scale && y > -(3.0f32.sqrt() / 2.0) * scale &&
+			y > -3.0f32.sqrt() * (x / scale + 0.5) * scale &&
+			y > -(-3.0f32.sqrt() * (x / scale - 0.5) * scale) {
+			clear_buffer[i * 4..i * 4 + 4].copy_from_slice(&[color[0], color[1], color[2], 0xff]);
+		} else {
+			clear_buffer[i * 4..i * 4 + 4].copy_from_slice(&[background[0], background[1], background[2], 0]);
+		}
+	}
+	clear_buffer
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	use super::*;
+
+	const SIZE: u32 = 64;
+
+	fn pixel(buffer: &[u8], x: u32, y: u32) -> &[u8] {
+		let i = (y * SIZE + x) as usize * 4;
+		&buffer[i..i + 4]
+	}
+
+	/// One generation of `transforms` copying an opaque `source` over an empty black seed.
+	fn generation(transforms: &[Transform], sampling: Sampling, mut source: Vec<u8>) -> Vec<u8> {
+		let feedback = Feedback { width: SIZE, height: SIZE, sampling, symmetry: Symmetry::default() };
+		let seed = vec![0; SIZE as usize * SIZE as usize * 4];
+		let mut dest = seed.clone();
+		let transforms: Vec<&Transform> = transforms.iter().collect();
+		feedback.step(&transforms, &seed, &mut source, &mut dest);
+		dest
+	}
+
+	fn white() -> Vec<u8> {
+		vec![0xff; SIZE as usize * SIZE as usize * 4]
+	}
+
+	#[test]
+	fn coverage_normalization_evens_out_contributions() {
+		let centered = |scale: f32, normalize_alpha_by_coverage| Transform {
+			position: Vec2::new(32.0, 32.0),
+			scale: Vec2::new(scale, scale),
+			alpha: 0x80,
+			normalize_alpha_by_coverage,
+			..Transform::identity()
+		};
+		let contribution = |scale, normalize| pixel(&generation(&[centered(scale, normalize)], Sampling::Nearest, white()), 32, 32)[0] as i32;
+		let (small, large) = (contribution(0.3, true), contribution(0.9, true));
+		assert!((small - large).abs() <= 2, "{} against {}", small, large);
+		// Without it the small copy stacks up to nearly opaque
+		let (small, large) = (contribution(0.3, false), contribution(0.9, false));
+		assert!(small - large > 64, "{} against {}", small, large);
+	}
+
+	/// An opaque seed that is different everywhere, so any symmetry has to come from the fold.
+	fn gradient() -> Vec<u8> {
+		(0..SIZE * SIZE).flat_map(|i| [(i % SIZE * 4) as u8, (i / SIZE * 3) as u8, (i * 7 % 251) as u8, 0xff]).collect()
+	}
+
+	#[test]
+	fn vertical_mirror_makes_the_output_symmetric() {
+		let mut world = FractalWorld::new(SIZE, SIZE, gradient());
+		world.add_transform(Transform {
+			position: Vec2::new(20.0, 24.0),
+			rotation: 0.4,
+			scale: Vec2::new(0.6, 0.45),
+			alpha: 0xc0,
+			..Transform::identity()
+		});
+		world.symmetry.vertical = true;
+		let mut frame = vec![0; SIZE as usize * SIZE as usize * 4];
+		for iterations in [1, 5] {
+			world.render_into(&mut frame, iterations);
+			for y in 0..SIZE {
+				for x in 0..SIZE / 2 {
+					assert_eq!(pixel(&frame, x, y), pixel(&frame, SIZE - 1 - x, y), "{},{} after {}", x, y, iterations);
+				}
+			}
+		}
+		// Only about the center column: top and bottom still differ
+		assert_ne!(pixel(&frame, 10, 5), pixel(&frame, 10, SIZE - 6));
+	}
+
+	/// Opaque white on the left half and opaque black on the right.
+	fn hard_edge() -> Vec<u8> {
+		(0..SIZE * SIZE).flat_map(|i| if i % SIZE < SIZE / 2 { [0xff, 0xff, 0xff, 0xff] } else { [0, 0, 0, 0xff] }).collect()
+	}
+
+	#[test]
+	fn nearest_keeps_edges_hard_and_smooth_grades_them() {
+		// Shrunk and turned, so destination pixels sample between source pixel centers
+		let transform = Transform { position: Vec2::new(32.0, 32.0), rotation: 0.3, scale: Vec2::new(0.7, 0.7), ..Transform::identity() };
+		let intermediate = |frame: &[u8]| frame.chunks_exact(4).filter(|pixel| pixel[0] > 0 && pixel[0] < 0xff).count();
+		let nearest = generation(&[transform], Sampling::Nearest, hard_edge());
+		assert_eq!(intermediate(&nearest), 0);
+		let smooth = generation(&[transform], Sampling::Smooth, hard_edge());
+		assert!(intermediate(&smooth) >= 20, "{} graded pixels", intermediate(&smooth));
+		// Away from the edge the two agree
+		assert_eq!(pixel(&nearest, 24, 32), pixel(&smooth, 24, 32));
+		assert_eq!(pixel(&nearest, 40, 32), pixel(&smooth, 40, 32));
+	}
+
+	/// Overlapping, tinted, partly transparent copies, so any change in blend order would show.
+	fn overlapping() -> Vec<Transform> {
+		vec![
+			Transform { position: Vec2::new(24.0, 28.0), rotation: 0.5, scale: Vec2::new(0.6, 0.5), alpha: 0xa0, tint: Some([0xff, 0x20, 0x20]), ..Transform::identity() },
+			Transform { position: Vec2::new(36.0, 34.0), rotation: -1.1, scale: Vec2::new(-0.55, 0.55), alpha: 0x70, ..Transform::identity() },
+			Transform { position: Vec2::new(30.0, 40.0), scale: Vec2::new(0.8, 0.4), alpha: 0xd0, weight: 0.6, tint: Some([0x20, 0x20, 0xff]), ..Transform::identity() },
+		]
+	}
+
+	#[test]
+	fn step_matches_a_serial_gather_over_every_pixel() {
+		for sampling in [Sampling::Nearest, Sampling::Smooth] {
+			let transforms = overlapping();
+			let mut source = gradient();
+			let stepped = generation(&transforms, sampling, source.clone());
+
+			// One pixel at a time, back to front, with no bounds culling
+			let mut expected = vec![0; source.len()];
+			let source = PixelGrid::new(&mut source, SIZE, SIZE, SIZE as usize * 4);
+			let mut dest = PixelGrid::new(&mut expected, SIZE, SIZE, SIZE as usize * 4);
+			let center = Vec2::new(SIZE as f32 / 2.0, SIZE as f32 / 2.0);
+			for y in 0..SIZE {
+				for x in 0..SIZE {
+					for transform in transforms.iter().rev() {
+						let source_point = transform.apply_inverse(Vec2::new(x as f32 + 0.5, y as f32 + 0.5)) + center;
+						let color = match sampling {
+							Sampling::Nearest => source.sample(source_point),
+							Sampling::Smooth => source.sample_bilinear(source_point),
+						};
+						if let Some(color) = color.filter(|color| color[3] > 0) {
+							let tinted = transform.tinted([color[0], color[1], color[2]]);
+							dest.composite_pixel(x, y, &tinted, transform.copy_alpha() * transform.weight * color[3] as f32 / 255.0);
+						}
+					}
+				}
+			}
+			assert!(stepped == expected, "{:?} differs from the serial gather", sampling);
+		}
+	}
+
+	#[cfg(feature = "parallel")]
+	#[test]
+	fn step_is_identical_on_any_number_of_threads() {
+		let on_threads = |threads| {
+			let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+			pool.install(|| generation(&overlapping(), Sampling::Smooth, gradient()))
+		};
+		let serial = on_threads(1);
+		for threads in [2, 3, 8] {
+			assert!(on_threads(threads) == serial, "{} threads differ from one", threads);
+		}
+	}
+
+	#[test]
+	fn axis_aligned_copies_reproduce_the_source() {
+		// Full size at the center is the source itself
+		let centered = Transform { position: Vec2::new(32.0, 32.0), ..Transform::identity() };
+		assert!(generation(&[centered], Sampling::Nearest, gradient()) == gradient());
+		// Half size lands every second source pixel, with nothing resampled in between
+		let halved = Transform { position: Vec2::new(32.0, 32.0), scale: Vec2::new(0.5, 0.5), ..Transform::identity() };
+		let frame = generation(&[halved], Sampling::Nearest, gradient());
+		let source = gradient();
+		for y in 16..48 {
+			for x in 16..48 {
+				assert_eq!(pixel(&frame, x, y), pixel(&source, (x - 16) * 2 + 1, (y - 16) * 2 + 1), "{},{}", x, y);
+			}
+		}
+		assert_eq!(pixel(&frame, 15, 15), [0, 0, 0, 0]);
+	}
+
+	#[test]
+	fn rotated_copy_leaves_no_holes() {
+		let rotated = Transform { position: Vec2::new(32.0, 32.0), rotation: 30f32.to_radians(), scale: Vec2::new(0.7, 0.7), ..Transform::identity() };
+		let frame = generation(&[rotated], Sampling::Nearest, white());
+		let center = Vec2::new(32.0, 32.0);
+		let mut covered = 0;
+		for y in 0..SIZE {
+			for x in 0..SIZE {
+				// Every pixel whose center lies inside the rotated canvas, a pixel in from its edge
+				let source = rotated.apply_inverse(Vec2::new(x as f32 + 0.5, y as f32 + 0.5)) + center;
+				if source.x > 1.0 && source.y > 1.0 && source.x < SIZE as f32 - 1.0 && source.y < SIZE as f32 - 1.0 {
+					assert_eq!(pixel(&frame, x, y), [0xff; 4], "hole at {},{}", x, y);
+					covered += 1;
+				}
+			}
+		}
+		assert!(covered > 1500, "only {} pixels inside the copy", covered);
+	}
+
+	/// An opaque white disc with a hard edge on transparent black, turned copies of it inside.
+	fn disc_world() -> FractalWorld {
+		let seed = (0..SIZE * SIZE).flat_map(|i| {
+			let (x, y) = ((i % SIZE) as f32 + 0.5 - 32.0, (i / SIZE) as f32 + 0.5 - 32.0);
+			if x.hypot(y) < 20.0 { [0xff; 4] } else { [0; 4] }
+		}).collect();
+		let mut world = FractalWorld::new(SIZE, SIZE, seed);
+		world.add_transform(Transform { position: Vec2::new(25.0, 30.0), rotation: 0.5, scale: Vec2::new(0.45, 0.3), alpha: 0x80, ..Transform::identity() });
+		world
+	}
+
+	#[test]
+	fn supersampling_without_jitter_is_a_single_pass() {
+		let world = disc_world();
+		let mut single = vec![0; SIZE as usize * SIZE as usize * 4];
+		world.render_into(&mut single, 4);
+		let mut averaged = vec![0; single.len()];
+		world.render_supersampled(&mut averaged, 4, 5, 0.0, &mut StdRng::seed_from_u64(7));
+		assert!(averaged == single);
+	}
+
+	#[test]
+	fn jittered_samples_soften_aliased_edges() {
+		// Mean squared step between neighbouring pixels: hard staircase edges step by the full range
+		let edge_variance = |frame: &[u8]| {
+			let steps: Vec<f32> = (0..SIZE - 1)
+				.flat_map(|y| (0..SIZE - 1).flat_map(move |x| [(x, y, x + 1, y), (x, y, x, y + 1)]))
+				.map(|(x0, y0, x1, y1)| pixel(frame, x0, y0)[0] as f32 - pixel(frame, x1, y1)[0] as f32)
+				.filter(|&step| step != 0.0)
+				.collect();
+			steps.iter().map(|step| step * step).sum::<f32>() / steps.len() as f32
+		};
+		let world = disc_world();
+		let mut single = vec![0; SIZE as usize * SIZE as usize * 4];
+		world.render_into(&mut single, 4);
+		let mut jittered = vec![0; single.len()];
+		world.render_supersampled(&mut jittered, 4, 16, 1.0, &mut StdRng::seed_from_u64(7));
+		let (aliased, smoothed) = (edge_variance(&single), edge_variance(&jittered));
+		assert!(smoothed < aliased / 4.0, "{} against {}", smoothed, aliased);
+		// The same seed gives the same jitter pattern
+		let mut again = vec![0; single.len()];
+		world.render_supersampled(&mut again, 4, 16, 1.0, &mut StdRng::seed_from_u64(7));
+		assert!(again == jittered);
+	}
+}
@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use image::imageops::{self, FilterType};
+use image::{ExtendedColorType, Rgba, RgbaImage};
+
+/// Decode the image at `path` into a `width`×`height` RGBA buffer, scaling it if its dimensions
+/// don't match.
+///
+/// The alpha channel becomes the feedback mask: fully transparent pixels and pixels of the
+/// `background` color are cleared to alpha 0 so they're never copied, everything else is opaque.
+/// The mask is computed once here rather than by comparing colors during feedback.
+pub fn load_canvas_image(path: &Path, width: u32, height: u32, background: [u8; 3]) -> image::ImageResult<Vec<u8>> {
+	let mut image = image::open(path)?.into_rgba8();
+	if image.dimensions() != (width, height) {
+		image = imageops::resize(&image, width, height, FilterType::Triangle);
+	}
+	let mut buffer = image.into_raw();
+	mask_background(&mut buffer, background);
+	Ok(buffer)
+}
+
+/// Decode the image at `path` as a seed: scaled to fit the canvas with its aspect ratio kept,
+/// centered, and letterboxed with `background`. Masked like `load_canvas_image`.
+pub fn load_seed_image(path: &Path, width: u32, height: u32, background: [u8; 3]) -> image::ImageResult<Vec<u8>> {
+	let image = image::open(path)?.into_rgba8();
+	let (image_width, image_height) = image.dimensions();
+	let fit = (width as f32 / image_width as f32).min(height as f32 / image_height as f32);
+	let fitted_width = ((image_width as f32 * fit).round() as u32).clamp(1, width);
+	let fitted_height = ((image_height as f32 * fit).round() as u32).clamp(1, height);
+	let image = imageops::resize(&image, fitted_width, fitted_height, FilterType::Triangle);
+	let mut canvas = RgbaImage::from_pixel(width, height, Rgba([background[0], background[1], background[2], 0]));
+	imageops::replace(&mut canvas, &image, ((width - fitted_width) / 2) as i64, ((height - fitted_height) / 2) as i64);
+	let mut buffer = canvas.into_raw();
+	mask_background(&mut buffer, background);
+	Ok(buffer)
+}
+
+/// Clear fully transparent and `background` colored pixels to alpha 0, make the rest opaque.
+fn mask_background(buffer: &mut [u8], background: [u8; 3]) {
+	for pixel in buffer.chunks_exact_mut(4) {
+		if pixel[3] == 0 || pixel[0..3] == background {
+			pixel.copy_from_slice(&[background[0], background[1], background[2], 0]);
+		} else {
+			pixel[3] = 0xff;
+		}
+	}
+}
+
+/// Write a `width`×`height` RGBA buffer to `path` as a PNG.
+pub fn save_canvas_png(path: &Path, buffer: &[u8], width: u32, height: u32) -> image::ImageResult<()> {
+	image::save_buffer(path, buffer, width, height, ExtendedColorType::Rgba8)
+}
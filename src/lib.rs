@@ -0,0 +1,18 @@
+//! Iterated function system fractals grown by feedback: a seed image with scaled, rotated copies
+//! of the previous generation composited over it, repeated until it converges on the attractor.
+//!
+//! `fractal::FractalWorld` renders a composition offscreen; the `rtfractal` binary is an
+//! interactive editor built on top of it.
+
+#![deny(clippy::all)]
+// The C interface is the only place that handles raw pointers
+#![cfg_attr(not(feature = "capi"), forbid(unsafe_code))]
+#![cfg_attr(feature = "capi", deny(unsafe_code))]
+
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod chaos;
+pub mod fractal;
+pub mod pixel;
+
+pub use fractal::FractalWorld;
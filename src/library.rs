@@ -0,0 +1,85 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::pixel::Transform;
+use crate::save::SavedTransform;
+
+pub const LIBRARY_PATH: &str = "library.json";
+
+#[derive(Serialize, Deserialize)]
+pub struct LibraryEntry {
+	pub name: String,
+	pub transform: SavedTransform,
+}
+
+/// Named transforms bookmarked for reuse across compositions.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Library {
+	pub entries: Vec<LibraryEntry>,
+}
+
+impl Library {
+	/// Read the library at `path`, starting an empty one if the file doesn't exist yet.
+	pub fn load(path: &Path) -> io::Result<Self> {
+		let contents = match fs::read_to_string(path) {
+			Ok(contents) => contents,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+			Err(e) => return Err(e),
+		};
+		let mut library: Self = serde_json::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+		for entry in &mut library.entries {
+			entry.transform = entry.transform.validated().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+		}
+		Ok(library)
+	}
+
+	pub fn save(&self, path: &Path) -> io::Result<()> {
+		let contents = serde_json::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+		fs::write(path, contents)
+	}
+
+	pub fn bookmark(&mut self, transform: &Transform) -> &LibraryEntry {
+		let name = format!("bookmark {}", self.entries.len() + 1);
+		self.entries.push(LibraryEntry { name, transform: transform.into() });
+		&self.entries[self.entries.len() - 1]
+	}
+
+	pub fn instantiate(&self, index: usize) -> Option<Transform> {
+		self.entries.get(index).map(|entry| entry.transform.into())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::pixel::Vec2;
+
+	#[test]
+	fn bookmark_then_instantiate_reproduces_the_transform() {
+		let transform = Transform {
+			position: Vec2::new(312.25, 87.5),
+			rotation: -1.234_567,
+			scale: Vec2::new(0.437, -0.81),
+			alpha: 0xc3,
+			normalize_alpha_by_coverage: true,
+			tint: Some([0x2A, 0x9D, 0x8F]),
+			weight: 0.3,
+		};
+		let mut library = Library::default();
+		library.bookmark(&Transform::identity());
+		library.bookmark(&transform);
+		assert_eq!(library.instantiate(1), Some(transform));
+		// And through the file, which is where bookmarks are picked from later
+		let path = std::env::temp_dir().join(format!("rtfractal-library-test-{}.json", std::process::id()));
+		library.save(&path).unwrap();
+		let loaded = Library::load(&path);
+		fs::remove_file(&path).unwrap();
+		let loaded = loaded.unwrap();
+		assert_eq!(loaded.entries[1].name, "bookmark 2");
+		assert_eq!(loaded.instantiate(1), Some(transform));
+		assert_eq!(loaded.instantiate(2), None);
+	}
+}
@@ -2,21 +2,24 @@
 #![forbid(unsafe_code)]
 
 use log::error;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use pixels::{Error, Pixels, SurfaceTexture};
 use winit::dpi::LogicalSize;
-use winit::event::{Event, VirtualKeyCode};
+use winit::event::{Event, VirtualKeyCode, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::WindowBuilder;
 use winit_input_helper::WinitInputHelper;
-use std::time::{Instant, Duration};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, Duration, SystemTime, UNIX_EPOCH};
+use rtfractal::fractal::{self, FractalWorld, Sampling, Symmetry};
+use rtfractal::{chaos, pixel};
 
-mod pixel;
-
-const WIDTH: u32 = 1000;
-const HEIGHT: u32 = 1000;
-// const WIDTH: u32 = 320;
-// const HEIGHT: u32 = 240;
-const BOX_SIZE: i16 = 17;
+mod args;
+mod image_io;
+mod library;
+mod save;
 
 #[derive(Eq, PartialEq, Copy, Clone)]
 enum WorldHoverMode {
@@ -25,10 +28,125 @@ enum WorldHoverMode {
 
 /// Representation of the application state. In this example, a box will bounce around the screen.
 struct World {
+	/// Front to back: the first transform gets input first, has its handles drawn on top and is
+	/// composited over the others in the feedback.
 	transforms: Vec<ScreenTransform>,
+	/// Whether clicking a transform brings it to the front.
+	raise_on_click: bool,
+	/// Whether the panel listing every transform is drawn, and the arrow keys move through it.
+	show_list: bool,
+	/// Where new color seeds come from, so a run seeded the same way rolls the same palette.
+	color_rng: StdRng,
+	/// Id the next new transform gets; see `TransformState::id`.
+	next_id: u64,
 	hovering: Option<WorldHoverMode>,
+	symmetry: Symmetry,
+	debug_cycle: Option<CycleState>,
+	sampling: Sampling,
+	history: History,
+	/// State from before the press that's under way, recorded once the button is up if the press
+	/// changed anything.
+	pending_edit: Option<Vec<TransformState>>,
+	/// Id of the transform the wheel has been scrolling over since the last recorded edit, so a
+	/// whole burst of wheel events undoes as one edit.
+	scroll_burst: Option<u64>,
+	/// Viewport camera between the canvas and the screen: a uniform zoom and an offset.
+	view: pixel::Transform,
+	/// The canvas as it was before `present` resampled it through `view`, kept between frames so
+	/// presenting doesn't allocate.
+	view_scratch: Vec<u8>,
+	/// Feedback generations run per frame.
+	iterations: u32,
+	/// Set when the transforms change, so the accumulated fractal can be restarted.
+	dirty: bool,
+	chaos: chaos::ChaosGame,
+	/// Canvas size in pixels.
+	width: u32,
+	height: u32,
+	palette: Palette,
+	/// Canvas-space corners of the selection rectangle being dragged: where it started and the cursor.
+	rubber_band: Option<(pixel::Vec2, pixel::Vec2)>,
+}
+
+/// Screen pixels the cursor has to travel before a press on empty canvas counts as a rubber band.
+const RUBBER_BAND_MIN_DRAG: f32 = 4.0;
+const UNDO_LIMIT: usize = 100;
+const MAX_ITERATIONS: u32 = 16;
+const CHAOS_POINTS_PER_FRAME: usize = 200_000;
+const CHAOS_INK_COLOR: [u8; 3] = [0x13, 0x1B, 0x23];
+const CHAOS_LIGHT_INK_COLOR: [u8; 3] = [0xF0, 0xF0, 0xF0];
+/// Distance of the FPS counter and the library picker from their corners of the window.
+const FPS_MARGIN: f32 = 10.0;
+/// Screen pixels between the list panel and the left edge of the window.
+const LIST_MARGIN: f32 = 10.0;
+/// Top of the list panel's first line, below the add button.
+const LIST_TOP: f32 = 100.0;
+/// Zoom factor per scroll wheel notch.
+const ZOOM_STEP: f32 = 1.1;
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 16.0;
+
+/// Snapshots of the transform list for undo and redo.
+#[derive(Default)]
+struct History {
+	undo: Vec<Vec<TransformState>>,
+	redo: Vec<Vec<TransformState>>,
+}
+
+impl History {
+	/// Remember `snapshot` as the state before an edit, dropping the redo branch.
+	fn record(&mut self, snapshot: Vec<TransformState>) {
+		self.redo.clear();
+		self.undo.push(snapshot);
+		if self.undo.len() > UNDO_LIMIT {
+			self.undo.remove(0);
+		}
+	}
+
+	fn undo(&mut self, current: Vec<TransformState>) -> Option<Vec<TransformState>> {
+		let previous = self.undo.pop()?;
+		self.redo.push(current);
+		Some(previous)
+	}
+
+	fn redo(&mut self, current: Vec<TransformState>) -> Option<Vec<TransformState>> {
+		let next = self.redo.pop()?;
+		self.undo.push(current);
+		Some(next)
+	}
+}
+
+/// Debug playback that solos each transform in turn, then shows them all together.
+struct CycleState {
+	/// Index of the soloed transform among the enabled ones, or their count for the "all enabled"
+	/// step.
+	step: usize,
+	elapsed: Duration,
+	interval: Duration,
+}
+
+impl CycleState {
+	fn new(interval: Duration) -> Self {
+		Self { step: 0, elapsed: Duration::ZERO, interval }
+	}
+
+	/// Advance by `delta`, moving on to the next step every `interval` and wrapping after the
+	/// "all enabled" step. Returns whether the step changed.
+	fn advance(&mut self, delta: Duration, transform_count: usize) -> bool {
+		self.elapsed += delta;
+		if self.elapsed < self.interval { return false }
+		self.elapsed -= self.interval;
+		self.step = (self.step + 1) % (transform_count + 1);
+		true
+	}
+
+	fn is_enabled(&self, index: usize, transform_count: usize) -> bool {
+		self.step >= transform_count || self.step == index
+	}
 }
 
+const DEBUG_CYCLE_INTERVAL: Duration = Duration::from_secs(2);
+
 #[derive(Eq, PartialEq, Copy, Clone)]
 enum EditMode {
 	Dual,
@@ -36,16 +154,94 @@ enum EditMode {
 	View,
 }
 
+/// What the feedback pass presents in View/Dual mode.
+#[derive(Eq, PartialEq, Copy, Clone)]
+enum ViewKind {
+	/// The full accumulated fractal.
+	Accumulated,
+	/// Only the pixels that changed since the previous generation.
+	GrowthDelta,
+}
+
+/// How the fractal is rendered in View/Dual mode.
+#[derive(Eq, PartialEq, Copy, Clone)]
+enum Renderer {
+	/// Copy the canvas through every transform each generation.
+	Feedback,
+	/// Plot the orbit of a single point under randomly picked transforms.
+	ChaosGame,
+}
+
+/// Canvas colors that can be changed at runtime; the handle colors stay fixed.
+#[derive(Copy, Clone)]
+struct Palette {
+	background: [u8; 3],
+	/// Color of the built-in seed triangle.
+	seed: [u8; 3],
+}
+
+impl Palette {
+	/// Color the chaos game shades dense regions towards: dark on light backgrounds, light on dark.
+	fn chaos_ink(&self) -> [u8; 3] {
+		let [r, g, b] = self.background.map(|c| c as u32);
+		if r * 299 + g * 587 + b * 114 >= 128 * 1000 { CHAOS_INK_COLOR } else { CHAOS_LIGHT_INK_COLOR }
+	}
+}
+
+const DEFAULT_PALETTE: Palette = Palette {
+	background: [0xE3, 0xE3, 0xE3],
+	seed: [0x23, 0xA9, 0x50],
+};
+/// Backgrounds B cycles through, starting from the default grey.
+const BACKGROUND_PRESETS: [[u8; 3]; 4] = [
+	[0xE3, 0xE3, 0xE3],
+	[0x00, 0x00, 0x00],
+	[0xFF, 0xFF, 0xFF],
+	[0x1B, 0x23, 0x30],
+];
+/// Seed colors Shift+B cycles through, starting from the default green.
+const SEED_PRESETS: [[u8; 3]; 5] = [
+	[0x23, 0xA9, 0x50],
+	[0xF2, 0xC1, 0x2E],
+	[0x3D, 0x8B, 0xFF],
+	[0xDB, 0x32, 0x4D],
+	[0xFF, 0xFF, 0xFF],
+];
+
+/// The preset after `current` in `presets`, wrapping around; the first one if `current` isn't one.
+fn next_preset(presets: &[[u8; 3]], current: [u8; 3]) -> [u8; 3] {
+	let next = presets.iter().position(|&p| p == current).map_or(0, |i| (i + 1) % presets.len());
+	presets[next]
+}
+
+/// Smallest width and height a canvas following the window is shrunk to.
+const MIN_CANVAS_SIZE: u32 = 200;
+
 fn main() -> Result<(), Error> {
 	env_logger::init();
+	let args = match args::Command::parse(std::env::args().skip(1)) {
+		Ok(args::Command::Interactive(args)) => args,
+		Ok(args::Command::Render(args)) => {
+			if let Err(e) = render_headless(&args) {
+				eprintln!("error: {}", e);
+				std::process::exit(1);
+			}
+			return Ok(());
+		},
+		Err(e) => {
+			eprintln!("error: {}", e);
+			std::process::exit(2);
+		},
+	};
+	let (mut width, mut height) = args.size;
 	let event_loop = EventLoop::new();
 	let mut input = WinitInputHelper::new();
 	let window = {
-		let size = LogicalSize::new(WIDTH as f64, HEIGHT as f64);
+		let size = LogicalSize::new(width as f64, height as f64);
 		WindowBuilder::new()
 			.with_title("Fractorio")
 			.with_inner_size(size)
-			.with_min_inner_size(size)
+			.with_min_inner_size(if args.fit_window { LogicalSize::new(MIN_CANVAS_SIZE as f64, MIN_CANVAS_SIZE as f64) } else { size })
 			.build(&event_loop)
 			.unwrap()
 	};
@@ -53,41 +249,101 @@ fn main() -> Result<(), Error> {
 	let mut pixels = {
 		let window_size = window.inner_size();
 		let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
-		Pixels::new(WIDTH, HEIGHT, surface_texture)?
+		Pixels::new(width, height, surface_texture)?
 	};
-	let mut world = World::new();
-
-	let mut clear_buffer = Box::new([0u8; (WIDTH * HEIGHT * 4) as usize]);
-	for i in 0..(WIDTH * HEIGHT) as usize {
-		let x = (i % WIDTH as usize) as u32;
-		let y = (i / WIDTH as usize) as u32;
-		// if x > WIDTH * 4 / 10 && x < WIDTH * 6 / 10 &&
-			// y > HEIGHT * 4 / 10 && y < HEIGHT * 6 / 10 {
-		let x = x as f32 - WIDTH as f32 / 2.0;
-		let y = y as f32 - HEIGHT as f32 / 2.0;
-		let scale = 200.0;
-		if 	x > -0.5 * scale && x < 0.5 * scale &&
-			y < 0.0 * scale && y > -(3.0f32.sqrt() / 2.0) * scale &&
-			y > -3.0f32.sqrt() * (x / scale + 0.5) * scale &&
-			y > -(-3.0f32.sqrt() * (x / scale - 0.5) * scale) {
-			clear_buffer[i * 4..i * 4 + 4].copy_from_slice(&[0x23, 0xA9, 0x50, 0xff]);
-		} else {
-			clear_buffer[i * 4..i * 4 + 4].copy_from_slice(&[0xE3, 0xE3, 0xE3, 0xff]);
+	let mut world = World::new(width, height);
+	if let Some(seed) = args.color_seed {
+		world.reseed_colors(seed);
+	}
+	if let Some(path) = &args.load {
+		match save::SavedWorld::load(path) {
+			Ok(saved) => world.load_saved(saved),
+			Err(e) => {
+				eprintln!("error: failed to load {}: {}", path.display(), e);
+				std::process::exit(1);
+			},
 		}
 	}
+	// Loading at startup isn't an edit; keep any --continue image
+	world.take_dirty();
+	let save_path = Path::new(save::SAVE_PATH);
+
+	let library_path = Path::new(library::LIBRARY_PATH);
+	let mut library = library::Library::load(library_path).unwrap_or_else(|e| {
+		error!("Failed to load {}: {}", library_path.display(), e);
+		library::Library::default()
+	});
+	let mut library_cursor = 0;
 
-	let mut last_frame_buffer = Box::new([0u8; (WIDTH * HEIGHT * 4) as usize]);
+	let mut seed_path = args.seed.clone();
+	let mut clear_buffer = seed_buffer(width, height, seed_path.as_deref(), world.palette).unwrap_or_else(|e| {
+		eprintln!("error: failed to load seed image: {}", e);
+		std::process::exit(1);
+	});
+
+	let mut last_frame_buffer = vec![0u8; width as usize * height as usize * 4];
+	if let Some(path) = &args.continue_from {
+		match image_io::load_canvas_image(path, width, height, world.palette.background) {
+			Ok(image) => last_frame_buffer.copy_from_slice(&image),
+			Err(e) => {
+				eprintln!("error: failed to load {}: {}", path.display(), e);
+				std::process::exit(1);
+			},
+		}
+	}
 
 	let mut edit_mode = EditMode::Dual;
+	let mut view_kind = ViewKind::Accumulated;
+	let mut renderer = Renderer::Feedback;
+	// Two stored transform sets an animation is recorded between
+	let mut keyframes: [Option<Vec<TransformState>>; 2] = [None, None];
+
+	let mut idle = IdleTracker::default();
+	let mut last_mouse_pos: Option<(f32, f32)> = None;
 
 	let mut last_frame = Instant::now();
 	let mut cumulative_delta = Duration::from_secs_f64(0.0);
 	let mut frame_count = 0;
 	let max_frame_count = 60;
+	let mut fps = 0.0;
+	let mut show_fps = false;
 	event_loop.run(move |event, _, control_flow| {
+		// Any window input resumes evolution
+		if let Event::WindowEvent { .. } = event {
+			idle.wake();
+			*control_flow = ControlFlow::Poll;
+		}
+		// The release may go to another window once focus is lost, so end any drag now
+		if let Event::WindowEvent { event: WindowEvent::Focused(false), .. } = event {
+			world.release_grabs();
+		}
+
 		// Draw the current frame
 		if let Event::RedrawRequested(_) = event {
-			world.draw(&clear_buffer, pixels.get_frame(), &mut last_frame_buffer, edit_mode);
+			// Regrow from the seed after an edit so no trails of the old arrangement linger
+			if world.take_dirty() {
+				last_frame_buffer.fill(0);
+				world.chaos.reset();
+			}
+			let view = world.view;
+			let changed_pixels = match renderer {
+				Renderer::Feedback => world.draw(&clear_buffer, pixels.get_frame(), &mut last_frame_buffer, edit_mode, view_kind, &view),
+				Renderer::ChaosGame => world.draw_chaos(&clear_buffer, pixels.get_frame(), edit_mode, &view),
+			};
+			idle.record(changed_pixels);
+			if show_fps {
+				let text = format!("{:.1} fps", fps);
+				let mut grid = pixel::PixelGrid::new(pixels.get_frame(), width, height, width as usize * 4);
+				grid.draw_text( pixel::Vec2::new(width as f32 - pixel::text_width(&text) - FPS_MARGIN, FPS_MARGIN),
+								&text,
+								&UNHOVERABLE_COLOR);
+			}
+			if let Some(text) = library_label(&library, library_cursor).filter(|_| edit_mode != EditMode::View) {
+				let mut grid = pixel::PixelGrid::new(pixels.get_frame(), width, height, width as usize * 4);
+				grid.draw_text( pixel::Vec2::new(FPS_MARGIN, height as f32 - pixel::TEXT_HEIGHT - FPS_MARGIN),
+								&text,
+								&UNHOVERABLE_COLOR);
+			}
 			if pixels
 				.render()
 				.map_err(|e| error!("pixels.render() failed: {}", e))
@@ -105,14 +361,18 @@ fn main() -> Result<(), Error> {
 			cumulative_delta += delta_frame;
 			frame_count += 1;
 			if frame_count == max_frame_count {
-				println!("{:?}", cumulative_delta / max_frame_count);
+				println!("{:?} ({} iterations)", cumulative_delta / max_frame_count, world.iterations);
+				fps = max_frame_count as f32 / cumulative_delta.as_secs_f32();
 				frame_count = 0;
 				cumulative_delta = Duration::from_secs_f64(0.0);
 			}
 			last_frame = current_frame;
+			world.advance_debug_cycle(delta_frame);
 
-			// Close events
-			if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
+			// Escape drops the selection first and only quits once nothing is selected
+			if input.key_pressed(VirtualKeyCode::Escape) && world.has_selection() {
+				world.clear_selection();
+			} else if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
 				*control_flow = ControlFlow::Exit;
 				return;
 			} else if input.key_pressed(VirtualKeyCode::Key1) {
@@ -120,23 +380,202 @@ fn main() -> Result<(), Error> {
 			} else if input.key_pressed(VirtualKeyCode::Key2) {
 				edit_mode = EditMode::Edit;
 			} else if input.key_pressed(VirtualKeyCode::Key3) {
+				// View mode stops feeding the mouse to the transforms, so a drag would never see its release
+				world.release_grabs();
 				edit_mode = EditMode::View;
+			} else if input.key_pressed(VirtualKeyCode::Home) {
+				// Over a transform (or with one listed) Home sends it to the front; elsewhere it resets the view
+				if world.active_transform().is_some() || world.listed_index().is_some() {
+					world.reorder_active(|_, _| 0);
+				} else {
+					world.reset_view();
+				}
+			} else if input.key_pressed(VirtualKeyCode::End) {
+				world.reorder_active(|_, count| count - 1);
+			} else if input.key_pressed(VirtualKeyCode::PageUp) {
+				world.reorder_active(|i, _| i.saturating_sub(1));
+			} else if input.key_pressed(VirtualKeyCode::PageDown) {
+				world.reorder_active(|i, count| (i + 1).min(count - 1));
+			} else if input.key_pressed(VirtualKeyCode::X) {
+				world.roll_colors(input.held_shift());
+			} else if input.key_pressed(VirtualKeyCode::T) {
+				world.show_list = !world.show_list;
+			} else if world.show_list && input.key_pressed(VirtualKeyCode::Up) {
+				world.step_list(-1);
+			} else if world.show_list && input.key_pressed(VirtualKeyCode::Down) {
+				world.step_list(1);
+			} else if input.key_pressed(VirtualKeyCode::A) {
+				world.raise_on_click = !world.raise_on_click;
+				println!("Bring to front on click: {}", if world.raise_on_click { "on" } else { "off" });
+			} else if input.key_pressed(VirtualKeyCode::Period) {
+				world.iterations = (world.iterations + 1).min(MAX_ITERATIONS);
+			} else if input.key_pressed(VirtualKeyCode::Comma) {
+				world.iterations = (world.iterations - 1).max(1);
+			} else if input.key_pressed(VirtualKeyCode::R) {
+				// Start over from the seed, and the chaos game from an empty grid
+				last_frame_buffer.fill(0);
+				world.chaos.reset();
+			} else if input.key_pressed(VirtualKeyCode::C) {
+				renderer = match renderer {
+					Renderer::Feedback => Renderer::ChaosGame,
+					Renderer::ChaosGame => Renderer::Feedback,
+				};
+			} else if input.held_control() && input.key_pressed(VirtualKeyCode::K) {
+				// Recording blocks the event loop, so no input is handled until it's done
+				match &keyframes {
+					[Some(from), Some(to)] => match record_animation(&world, &clear_buffer, from, to) {
+						Ok(directory) => println!("Recorded {}", directory.display()),
+						Err(e) => error!("Failed to record animation: {}", e),
+					},
+					_ => println!("Store keyframes with K and Shift+K before recording"),
+				}
+			} else if input.key_pressed(VirtualKeyCode::K) {
+				let slot = if input.held_shift() { 1 } else { 0 };
+				keyframes[slot] = Some(world.snapshot());
+				println!("Stored keyframe {}", if slot == 0 { "A" } else { "B" });
+			} else if input.key_pressed(VirtualKeyCode::G) {
+				view_kind = match view_kind {
+					ViewKind::Accumulated => ViewKind::GrowthDelta,
+					ViewKind::GrowthDelta => ViewKind::Accumulated,
+				};
+			} else if input.key_pressed(VirtualKeyCode::M) {
+				if input.held_shift() {
+					world.symmetry.horizontal = !world.symmetry.horizontal;
+				} else {
+					world.symmetry.vertical = !world.symmetry.vertical;
+				}
+			} else if input.key_pressed(VirtualKeyCode::D) {
+				world.toggle_debug_cycle();
+			} else if input.key_pressed(VirtualKeyCode::I) {
+				world.sampling = match world.sampling {
+					Sampling::Nearest => Sampling::Smooth,
+					Sampling::Smooth => Sampling::Nearest,
+				};
+			} else if input.held_shift() && input.key_pressed(VirtualKeyCode::F) {
+				show_fps = !show_fps;
+			} else if input.key_pressed(VirtualKeyCode::F) {
+				world.flip_active(true);
+			} else if input.key_pressed(VirtualKeyCode::V) {
+				world.flip_active(false);
+			} else if input.key_pressed(VirtualKeyCode::L) {
+				world.toggle_lock_active();
+			} else if input.key_pressed(VirtualKeyCode::H) {
+				world.toggle_enabled_active();
+			} else if input.key_pressed(VirtualKeyCode::N) {
+				world.toggle_alpha_normalization();
+			} else if input.held_control() && input.key_pressed(VirtualKeyCode::Z) {
+				if input.held_shift() { world.redo(); } else { world.undo(); }
+			} else if input.held_control() && input.key_pressed(VirtualKeyCode::Y) {
+				world.redo();
+			} else if input.held_control() && input.key_pressed(VirtualKeyCode::S) {
+				match world.to_saved().save(save_path) {
+					Ok(()) => println!("Saved {}", save_path.display()),
+					Err(e) => error!("Failed to save {}: {}", save_path.display(), e),
+				}
+			} else if input.held_control() && input.key_pressed(VirtualKeyCode::O) {
+				match save::SavedWorld::load(save_path) {
+					Ok(saved) => {
+						world.load_saved(saved);
+						last_frame_buffer.fill(0);
+						println!("Loaded {}", save_path.display());
+					},
+					Err(e) => error!("Failed to load {}: {}", save_path.display(), e),
+				}
+			} else if input.key_pressed(VirtualKeyCode::P) {
+				let mut export_buffer = vec![0u8; width as usize * height as usize * 4];
+				match renderer {
+					Renderer::Feedback => {
+						// Run one more generation in View layout offscreen so the export has no handles
+						let mut export_last_frame = last_frame_buffer.clone();
+						world.draw(&clear_buffer, &mut export_buffer, &mut export_last_frame, EditMode::View, ViewKind::Accumulated, &pixel::Transform::identity());
+					},
+					Renderer::ChaosGame => world.chaos.grid().tonemap(&mut export_buffer, world.palette.background, world.palette.chaos_ink()),
+				}
+				let path = export_path();
+				match image_io::save_canvas_png(&path, &export_buffer, width, height) {
+					Ok(()) => println!("Exported {}", path.display()),
+					Err(e) => error!("Failed to export {}: {}", path.display(), e),
+				}
+			} else if input.held_control() && input.key_pressed(VirtualKeyCode::B) {
+				if let Some(transform) = world.active_transform() {
+					let name = library.bookmark(transform).name.clone();
+					library_cursor = library.entries.len() - 1;
+					match library.save(library_path) {
+						Ok(()) => println!("Bookmarked {}", name),
+						Err(e) => error!("Failed to save {}: {}", library_path.display(), e),
+					}
+				}
+			} else if input.key_pressed(VirtualKeyCode::B) {
+				// Cycle the seed color with Shift, otherwise the background, and regrow from the new seed
+				if input.held_shift() {
+					world.palette.seed = next_preset(&SEED_PRESETS, world.palette.seed);
+				} else {
+					world.palette.background = next_preset(&BACKGROUND_PRESETS, world.palette.background);
+				}
+				match seed_buffer(width, height, seed_path.as_deref(), world.palette) {
+					Ok(seed) => clear_buffer = seed,
+					Err(e) => error!("Failed to reload seed: {}", e),
+				}
+				last_frame_buffer.fill(0);
+				world.chaos.reset();
+			} else if input.key_pressed(VirtualKeyCode::Tab) && !library.entries.is_empty() {
+				library_cursor = (library_cursor + 1) % library.entries.len();
+			} else if input.key_pressed(VirtualKeyCode::Return) {
+				if let Some(transform) = library.instantiate(library_cursor) {
+					world.add_transform(transform);
+				}
+			} else if input.key_pressed(VirtualKeyCode::Delete) || input.key_pressed(VirtualKeyCode::Back) {
+				world.delete_selected();
+			} else if let Some(nudge) = pressed_nudge(&input) {
+				world.nudge_selected(nudge);
+			}
+
+			// Continue evolving from a dropped image, or with Shift restart from it as the seed
+			if let Some(path) = input.dropped_file() {
+				if input.held_shift() {
+					match image_io::load_seed_image(&path, width, height, world.palette.background) {
+						Ok(seed) => {
+							clear_buffer.copy_from_slice(&seed);
+							last_frame_buffer.fill(0);
+							seed_path = Some(path);
+						},
+						Err(e) => error!("Failed to load {}: {}", path.display(), e),
+					}
+				} else {
+					match image_io::load_canvas_image(&path, width, height, world.palette.background) {
+						Ok(image) => last_frame_buffer.copy_from_slice(&image),
+						Err(e) => error!("Failed to load {}: {}", path.display(), e),
+					}
+				}
 			}
 
-			// Resize the window
+			// Resize the window, and with --fit-window the canvas along with it
 			if let Some(size) = input.window_resized() {
 				pixels.resize_surface(size.width, size.height);
+				if args.fit_window {
+					let logical: LogicalSize<u32> = size.to_logical(window.scale_factor());
+					let new_width = logical.width.max(MIN_CANVAS_SIZE);
+					let new_height = logical.height.max(MIN_CANVAS_SIZE);
+					if (new_width, new_height) != (width, height) {
+						width = new_width;
+						height = new_height;
+						pixels.resize_buffer(width, height);
+						world.resize(width, height);
+						clear_buffer = seed_buffer(width, height, seed_path.as_deref(), world.palette).unwrap_or_else(|e| {
+							error!("Failed to reload seed: {}", e);
+							fractal::seed_triangle(width, height, world.palette.seed, world.palette.background)
+						});
+						last_frame_buffer = vec![0u8; width as usize * height as usize * 4];
+					}
+				}
 			}
 
-			let mouse_state = if input.mouse_pressed(0) {
-				MouseClickState::Pressed
-			} else if input.mouse_released(0) {
-				MouseClickState::Released
-			} else if input.mouse_held(0) {
-				MouseClickState::Held
-			} else {
-				MouseClickState::Idle
-			};
+			// Space turns the left button into a pan, so it must not reach the transforms
+			let space_panning = input.key_held(VirtualKeyCode::Space);
+			let mouse_state = MouseClickState::from_buttons(space_panning,
+															input.mouse_pressed(0),
+															input.mouse_released(0),
+															input.mouse_held(0));
 
 			let mouse_pos = if let Some(mouse_pos) = input.mouse() {
 				if let Ok((x, y)) = pixels.window_pos_to_pixel(mouse_pos) {
@@ -144,16 +583,154 @@ fn main() -> Result<(), Error> {
 				} else { None }
 			} else { None };
 
+			if let Some((x, y)) = mouse_pos {
+				// Over a transform the wheel scales it, or rotates it with Shift; elsewhere it zooms
+				let scroll = input.scroll_diff();
+				let editing = edit_mode != EditMode::View;
+				if scroll != 0.0 && !(editing && world.scroll_hovered(scroll, input.held_shift())) {
+					world.zoom_view(pixel::Vec2::new(x, y), scroll);
+				}
+				if let Some((last_x, last_y)) = last_mouse_pos {
+					if input.mouse_held(2) || (space_panning && input.mouse_held(0)) {
+						world.pan_view(pixel::Vec2::new(x - last_x, y - last_y));
+					}
+				}
+			}
+			last_mouse_pos = mouse_pos;
+
 			// Update internal state and request a redraw
 			if edit_mode != EditMode::View {
-				world.update(mouse_pos, mouse_state);
+				world.update(mouse_pos, mouse_state, input.held_shift() || input.held_control());
+			}
+			// Once converged, stop re-running the feedback until input arrives
+			*control_flow = idle.control_flow(world.debug_cycle.is_some());
+			if *control_flow == ControlFlow::Poll {
+				window.request_redraw();
 			}
-			window.request_redraw();
 		}
 	});
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+/// Render the composition named by `args` to a PNG without opening a window, running the same
+/// feedback as the interactive View mode.
+fn render_headless(args: &args::RenderArgs) -> Result<(), String> {
+	let (width, height) = args.size;
+	let saved = if args.input == Path::new("-") {
+		let stdin = std::io::stdin();
+		// Waiting for a terminal to reach end of file would look like a hang
+		if stdin.is_terminal() { return Err("--input - reads the composition from stdin; pipe one in".to_string()) }
+		save::SavedWorld::from_reader(stdin.lock()).map_err(|e| format!("failed to read a composition from stdin: {}", e))?
+	} else {
+		save::SavedWorld::load(&args.input).map_err(|e| format!("failed to load {}: {}", args.input.display(), e))?
+	};
+	let mut world = World::new(width, height);
+	world.load_saved(saved);
+	let clear_buffer = seed_buffer(width, height, args.seed.as_deref(), world.palette).map_err(|e| format!("failed to load seed image: {}", e))?;
+	let frame = world.render_still(&clear_buffer, &world.rendered_transforms(), args.iterations.max(1), args.samples);
+	image_io::save_canvas_png(&args.out, &frame, width, height).map_err(|e| format!("failed to write {}: {}", args.out.display(), e))?;
+	println!("Rendered {}", args.out.display());
+	Ok(())
+}
+
+/// Seed of the jitter pattern supersampled renders are averaged over.
+const JITTER_SEED: u64 = 0x5EED;
+
+/// Frames in a recorded keyframe animation.
+const ANIMATION_FRAMES: u32 = 60;
+/// Feedback generations each animation frame is grown from the seed with.
+const ANIMATION_ITERATIONS: u32 = 12;
+
+/// Render `ANIMATION_FRAMES` frames morphing keyframe `from` into `to` and write them as a
+/// numbered PNG sequence into a new timestamped directory, which is returned.
+fn record_animation(world: &World, clear_buffer: &[u8], from: &[TransformState], to: &[TransformState]) -> Result<PathBuf, String> {
+	if from.len() != to.len() {
+		return Err(format!("keyframes have {} and {} transforms; they need the same number", from.len(), to.len()));
+	}
+	let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+	let directory = PathBuf::from(format!("rtfractal-animation-{}", timestamp.as_millis()));
+	std::fs::create_dir(&directory).map_err(|e| format!("failed to create {}: {}", directory.display(), e))?;
+	for i in 0..ANIMATION_FRAMES {
+		let t = i as f32 / (ANIMATION_FRAMES - 1) as f32;
+		let frame = world.render_still(clear_buffer, &keyframe_transforms(from, to, t), ANIMATION_ITERATIONS, 1);
+		let path = directory.join(format!("frame-{:04}.png", i));
+		image_io::save_canvas_png(&path, &frame, world.width, world.height).map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+		print!("\rRecording frame {}/{}", i + 1, ANIMATION_FRAMES);
+		let _ = std::io::Write::flush(&mut std::io::stdout());
+	}
+	println!();
+	Ok(directory)
+}
+
+/// The transforms `t` of the way from keyframe `from` to `to`. Like `Transform::lerp`'s flags, a
+/// transform is enabled when it is in the nearer keyframe.
+fn keyframe_transforms(from: &[TransformState], to: &[TransformState], t: f32) -> Vec<pixel::Transform> {
+	from.iter()
+		.zip(to)
+		.filter(|(a, b)| if t < 0.5 { a.enabled } else { b.enabled })
+		.map(|(a, b)| a.transform.lerp(&b.transform, t))
+		.collect()
+}
+
+/// The canvas to start feedback from: the image at `seed` fitted to the canvas, or the built-in
+/// triangle.
+fn seed_buffer(width: u32, height: u32, seed: Option<&Path>, palette: Palette) -> image::ImageResult<Vec<u8>> {
+	match seed {
+		Some(path) => image_io::load_seed_image(path, width, height, palette.background),
+		None => Ok(fractal::seed_triangle(width, height, palette.seed, palette.background)),
+	}
+}
+
+/// The bookmark Enter would add, as the picker shows it in the bottom left corner.
+fn library_label(library: &library::Library, cursor: usize) -> Option<String> {
+	let entry = library.entries.get(cursor)?;
+	Some(format!("library {}/{}: {}", cursor + 1, library.entries.len(), entry.name))
+}
+
+/// A timestamped file name so repeated exports don't overwrite each other.
+fn export_path() -> PathBuf {
+	let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+	PathBuf::from(format!("rtfractal-{}.png", timestamp.as_millis()))
+}
+
+/// Changed-pixel count at or below which a generation counts as converged.
+const IDLE_PIXEL_EPSILON: usize = 100;
+/// Consecutive converged generations before the app idles.
+const IDLE_FRAMES: usize = 30;
+
+/// Tracks convergence of the feedback loop so the event loop can idle once nothing changes.
+#[derive(Default)]
+struct IdleTracker {
+	converged_frames: usize,
+}
+
+impl IdleTracker {
+	fn record(&mut self, changed_pixels: usize) {
+		if changed_pixels <= IDLE_PIXEL_EPSILON {
+			self.converged_frames += 1;
+		} else {
+			self.converged_frames = 0;
+		}
+	}
+
+	fn wake(&mut self) {
+		self.converged_frames = 0;
+	}
+
+	fn is_idle(&self) -> bool {
+		self.converged_frames >= IDLE_FRAMES
+	}
+
+	/// Wait for input once converged, unless something keeps changing the image without input.
+	fn control_flow(&self, animating: bool) -> ControlFlow {
+		if self.is_idle() && !animating {
+			ControlFlow::Wait
+		} else {
+			ControlFlow::Poll
+		}
+	}
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 enum MouseClickState {
 	Pressed,
 	Held,
@@ -161,42 +738,141 @@ enum MouseClickState {
 	Idle,
 }
 
-const T_SCALE: f32 = 0.5436890127;
+impl MouseClickState {
+	/// The left button's state this frame from what the input helper saw of it. A button that is
+	/// neither pressed, released nor held is `Idle`, which ends any grab whose release went missing.
+	fn from_buttons(space_panning: bool, pressed: bool, released: bool, held: bool) -> Self {
+		if space_panning {
+			MouseClickState::Idle
+		} else if pressed {
+			MouseClickState::Pressed
+		} else if released {
+			MouseClickState::Released
+		} else if held {
+			MouseClickState::Held
+		} else {
+			MouseClickState::Idle
+		}
+	}
+}
+
+const T_SCALE: f32 = 0.543_689;
+/// Range the scale handle keeps transforms within.
+const MIN_SCALE: f32 = 0.1;
+const MAX_SCALE: f32 = 1.0;
 impl World {
 	/// Create a new `World` instance that can draw a moving box.
-	fn new() -> Self {
-		let mut transforms = Vec::new();
-		transforms.push(ScreenTransform { transform: pixel::Transform {
-				position: pixel::Vec2::new(WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0),
+	fn new(width: u32, height: u32) -> Self {
+		let mut world = Self {
+			transforms: Vec::new(),
+			raise_on_click: true,
+			show_list: false,
+			color_rng: StdRng::seed_from_u64(DEFAULT_COLOR_SEED),
+			next_id: 0,
+			hovering: None,
+			symmetry: Symmetry::default(),
+			debug_cycle: None,
+			sampling: Sampling::Nearest,
+			history: History::default(),
+			pending_edit: None,
+			scroll_burst: None,
+			view: pixel::Transform::identity(),
+			view_scratch: Vec::new(),
+			iterations: 1,
+			dirty: false,
+			chaos: chaos::ChaosGame::new(width, height),
+			width,
+			height,
+			rubber_band: None,
+			palette: DEFAULT_PALETTE,
+		};
+		let first = world.spawn(pixel::Transform {
+				position: pixel::Vec2::new(width as f32 / 2.0, height as f32 / 2.0),
 				rotation: 0.0,
-				scale: T_SCALE,
+				scale: pixel::Vec2::new(T_SCALE, T_SCALE),
 				alpha: 0xf0,
-			}, hovering: None, grabbing: None, scale_start: None, controls_visible: false, dead: false});
-		Self {
-			transforms,
-			hovering: None,
-		}
+				normalize_alpha_by_coverage: false,
+				tint: None,
+				weight: 1.0,
+			});
+		world.transforms.push(first);
+		world
 	}
 
-	fn update(&mut self, mouse_pos: Option<(f32, f32)>, mouse_state: MouseClickState) {
+	/// Feed the cursor to the transforms and the add button; `snap` is whether the snap modifier is held.
+	fn update(&mut self, mouse_pos: Option<(f32, f32)>, mouse_state: MouseClickState, snap: bool) {
+		// Grabs and deletes start on press; keep the state from before so they can be undone
+		let before = self.snapshot();
+		if mouse_state == MouseClickState::Pressed { self.pending_edit = Some(before.clone()); }
+		// Transforms live in canvas space, so undo the camera before hit-testing
+		let canvas_pos = mouse_pos.map(|(x, y)| self.view.apply_inverse(pixel::Vec2::new(x, y)));
+		if self.update_rubber_band(canvas_pos, mouse_state) { return }
+		// Releases off the window come without a position, and one can be missed altogether; either
+		// way the button is up, so nothing may stay grabbed
+		if mouse_state == MouseClickState::Idle || (mouse_state == MouseClickState::Released && canvas_pos.is_none()) {
+			self.release_grabs();
+		}
+
+		// A grab keeps its transform first in line even when it isn't in front, as in a group drag
+		let grabbed = self.transforms.iter().position(|t| t.grabbing.is_some());
+		let order: Vec<usize> = grabbed.into_iter().chain((0..self.transforms.len()).filter(|&i| Some(i) != grabbed)).collect();
 		let mut first_one = None;
-		let mut dead_ones = Vec::new();
-		for (i, transform) in self.transforms.iter_mut().enumerate() {
-			if transform.dead { dead_ones.push(i); }
-			if let Some((x, y)) = mouse_pos {
-				if transform.mouse_input(pixel::Vec2::new(x, y), mouse_state) {
-					first_one = Some(i);
-					break
+		let mut duplicate = false;
+		let mut cycle_tint = false;
+		let mut toggle_lock = false;
+		let mut moved = pixel::Vec2::new(0.0, 0.0);
+		for i in order {
+			if let Some(pos) = canvas_pos {
+				let transform = &mut self.transforms[i];
+				let position = transform.transform.position;
+				match transform.mouse_input(pos, mouse_state, snap, self.width, self.height) {
+					MouseResponse::Ignored => (),
+					response => {
+						first_one = Some(i);
+						duplicate = response == MouseResponse::Duplicate;
+						cycle_tint = response == MouseResponse::CycleTint;
+						toggle_lock = response == MouseResponse::ToggleLock;
+						if transform.grabbing == Some(Hoverables::Translate) {
+							moved = transform.transform.position - position;
+						}
+						break
+					},
+				}
+			}
+		}
+		let mut front = first_one;
+		if let (Some(i), true) = (first_one, toggle_lock) {
+			self.transforms[i].toggle_lock();
+		} else if let Some(i) = first_one.filter(|&i| !self.transforms[i].locked) {
+			let in_group = self.transforms[i].selected && self.selection_count() > 1;
+			if in_group {
+				// The rest of the group follows the dragged one, and deleting one deletes them all
+				let dead = self.transforms[i].dead;
+				for transform in self.transforms.iter_mut().filter(|t| t.selected) {
+					if transform.grabbing.is_none() { transform.transform.position += moved; }
+					transform.dead |= dead;
+				}
+			} else if mouse_state == MouseClickState::Pressed {
+				// Bringing a lone transform to the front is fine, but would scramble a group's order
+				if self.raise_on_click {
+					let top = self.transforms.remove(i);
+					self.transforms.insert(0, top);
+					front = Some(0);
+				}
+				for (j, transform) in self.transforms.iter_mut().enumerate() {
+					transform.selected = Some(j) == front;
 				}
 			}
 		}
-		if let Some(i) = first_one {
-			let top = self.transforms.remove(i);
-			self.transforms.insert(0, top);
+		if let Some(i) = front {
+			if duplicate { self.duplicate(i); }
+			if cycle_tint { self.cycle_tint(i); }
 		}
-		for dead_one in dead_ones { self.transforms.remove(dead_one); }
+		// Remove deleted transforms after the reorder so indices never go stale
+		self.transforms.retain(|t| !t.dead);
 
-		if let Some(mouse_pos) = mouse_pos {
+		// A transform under the cursor takes priority over the add button
+		if let (Some(mouse_pos), None) = (mouse_pos, first_one) {
 			if 		mouse_pos.0 > 20.0 &&
 					mouse_pos.0 < 80.0 &&
 					mouse_pos.1 > 20.0 &&
@@ -205,139 +881,937 @@ impl World {
 			} else {
 				self.hovering = None;
 			}
+		} else {
+			self.hovering = None;
 		}
 		if self.hovering.is_some() && mouse_state == MouseClickState::Pressed {
-			self.transforms.push(ScreenTransform { transform: pixel::Transform {
-					// position: pixel::Vec2::new(	WIDTH as f32 / 2.0 - rand::random::<f32>() * 100.0 + 50.0,
-					// 							HEIGHT as f32 / 2.0 - rand::random::<f32>() * 100.0 + 50.0),
+			let transform = self.spawn(pixel::Transform {
+					// position: pixel::Vec2::new(	self.width as f32 / 2.0 - rand::random::<f32>() * 100.0 + 50.0,
+					// 							self.height as f32 / 2.0 - rand::random::<f32>() * 100.0 + 50.0),
 					// rotation: rand::random::<f32>() * 0.1 - 0.05,
 					// scale: rand::random::<f32>() * 0.1 + 0.495,
-					position: pixel::Vec2::new(	WIDTH as f32 / 2.0,
-												HEIGHT as f32 / 2.0),
+					position: pixel::Vec2::new(	self.width as f32 / 2.0,
+												self.height as f32 / 2.0),
 					rotation: 0.0,
-					scale: T_SCALE,
+					scale: pixel::Vec2::new(T_SCALE, T_SCALE),
 					alpha: 0xf0,
-				}, hovering: None, grabbing: None, scale_start: None, controls_visible: false, dead: false});
+					normalize_alpha_by_coverage: false,
+					tint: None,
+					weight: 1.0,
+				});
+			self.transforms.push(transform);
+		} else if mouse_state == MouseClickState::Pressed && first_one.is_none() {
+			if let Some(pos) = canvas_pos {
+				self.rubber_band = Some((pos, pos));
+			}
+		}
+
+		// Hovering alone leaves the fractal be, and so does a press that hasn't moved anything yet
+		if self.snapshot() != before {
+			self.dirty = true;
+		}
+		if !self.transforms.iter().any(|t| t.grabbing.is_some()) {
+			self.finish_edit();
+		}
+		// Leaving the transform ends the scroll burst
+		if let Some(id) = self.scroll_burst {
+			if !self.transforms.iter().any(|t| t.id == id && t.controls_visible) { self.scroll_burst = None; }
+		}
+	}
+
+	/// Drag out the rubber band started by a press on empty canvas, returning whether it took the
+	/// input. On release, or once the button is found up, every transform centered inside it
+	/// becomes the selection; releasing without having dragged clears the selection instead.
+	fn update_rubber_band(&mut self, pos: Option<pixel::Vec2>, mouse_state: MouseClickState) -> bool {
+		let (start, end) = match (&mut self.rubber_band, pos) {
+			(Some(band), Some(pos)) => { band.1 = pos; *band },
+			(Some(band), None) => *band,
+			(None, _) => return false,
+		};
+		if mouse_state == MouseClickState::Released || mouse_state == MouseClickState::Idle {
+			self.rubber_band = None;
+			let min = pixel::Vec2::new(start.x.min(end.x), start.y.min(end.y));
+			let max = pixel::Vec2::new(start.x.max(end.x), start.y.max(end.y));
+			let dragged = (end - start).magnitude() * self.view.scale.x.abs() >= RUBBER_BAND_MIN_DRAG;
+			for transform in &mut self.transforms {
+				let position = transform.transform.position;
+				transform.selected = dragged && !transform.locked &&
+					(min.x..=max.x).contains(&position.x) &&
+					(min.y..=max.y).contains(&position.y);
+			}
+		}
+		true
+	}
+
+	/// End every grab and drop any rubber band, as if the button had been released.
+	fn release_grabs(&mut self) {
+		for transform in &mut self.transforms {
+			transform.grabbing = None;
+			transform.scale_start = None;
+		}
+		self.rubber_band = None;
+		self.finish_edit();
+	}
+
+	/// Record the pending press as an edit if the transforms differ from before it.
+	fn finish_edit(&mut self) {
+		if let Some(snapshot) = self.pending_edit.take() {
+			if snapshot != self.snapshot() {
+				self.record_edit(snapshot);
+			}
+		}
+	}
+
+	fn selection_count(&self) -> usize {
+		self.transforms.iter().filter(|t| t.selected).count()
+	}
+
+	fn add_transform(&mut self, transform: pixel::Transform) {
+		self.record_edit(self.snapshot());
+		let transform = self.spawn(transform);
+		self.transforms.push(transform);
+	}
+
+	/// A new on-screen transform with an id of its own, and a color seed drawn from `color_rng`
+	/// unless it comes with a tint.
+	fn spawn(&mut self, transform: pixel::Transform) -> ScreenTransform {
+		self.next_id += 1;
+		let mut spawned = ScreenTransform::new(self.next_id - 1, transform);
+		if transform.tint.is_none() {
+			spawned.take_color_seed(self.color_rng.gen());
+		}
+		spawned
+	}
+
+	/// Restart `color_rng` from `seed` and redraw the seeded transforms' colors from it, front to
+	/// back, so they are the ones a run with that seed spawns.
+	fn reseed_colors(&mut self, seed: u64) {
+		self.color_rng = StdRng::seed_from_u64(seed);
+		for transform in self.transforms.iter_mut().filter(|t| t.color_seed.is_some()) {
+			transform.take_color_seed(self.color_rng.gen());
+		}
+	}
+
+	/// Insert a copy of transform `i`, nudged so it's visibly distinct, in front of it.
+	///
+	/// Like the other edits a click makes, it's recorded for undo along with the press.
+	fn duplicate(&mut self, i: usize) {
+		let (mut copy, color_seed) = match self.transforms.get(i) {
+			Some(original) => (original.transform, original.color_seed),
+			None => return,
+		};
+		copy.position += pixel::Vec2::new(DUPLICATE_OFFSET, DUPLICATE_OFFSET);
+		// Its color is the original's, seeded or not
+		self.next_id += 1;
+		let copy = ScreenTransform { color_seed, ..ScreenTransform::new(self.next_id - 1, copy) };
+		self.transforms.insert(i, copy);
+	}
+
+	/// Advance transform `i`'s tint to the next palette entry, wrapping back to no tint.
+	fn cycle_tint(&mut self, i: usize) {
+		let transform = match self.transforms.get_mut(i) {
+			Some(front) => {
+				// A tint picked by hand is no longer the seed's
+				front.color_seed = None;
+				&mut front.transform
+			},
+			None => return,
+		};
+		let next = match transform.tint {
+			None => 0,
+			Some(tint) => TINT_PALETTE.iter().position(|&p| p == tint).map_or(0, |i| i + 1),
+		};
+		transform.tint = TINT_PALETTE.get(next).copied();
+	}
+
+	/// Give the transform under the cursor, or with `all` every transform front to back, a new
+	/// color seed from `color_rng` and the tint that comes from it.
+	fn roll_colors(&mut self, all: bool) {
+		let snapshot = self.snapshot();
+		let mut rolled = false;
+		for transform in self.transforms.iter_mut().filter(|t| all || (t.controls_visible && !t.locked)) {
+			transform.take_color_seed(self.color_rng.gen());
+			rolled = true;
+		}
+		if rolled { self.record_edit(snapshot); }
+	}
+
+	/// Note an edit: push the state from before it onto the undo stack and mark the world dirty.
+	fn record_edit(&mut self, snapshot: Vec<TransformState>) {
+		self.history.record(snapshot);
+		self.scroll_burst = None;
+		self.dirty = true;
+	}
+
+	/// Whether the transforms changed since the last call, clearing the flag.
+	fn take_dirty(&mut self) -> bool {
+		std::mem::take(&mut self.dirty)
+	}
+
+	fn snapshot(&self) -> Vec<TransformState> {
+		self.transforms.iter().map(ScreenTransform::state).collect()
+	}
+
+	/// Go back to `snapshot`; transforms that are still around keep their selection.
+	fn restore(&mut self, snapshot: Vec<TransformState>) {
+		let selected: Vec<u64> = self.transforms.iter().filter(|t| t.selected).map(|t| t.id).collect();
+		self.transforms = snapshot.into_iter().map(|state| {
+			let mut transform = ScreenTransform::from_state(state);
+			transform.selected = selected.contains(&state.id);
+			transform
+		}).collect();
+		self.rubber_band = None;
+		self.pending_edit = None;
+		self.scroll_burst = None;
+		self.dirty = true;
+		self.hovering = None;
+	}
+
+	fn undo(&mut self) {
+		if let Some(snapshot) = self.history.undo(self.snapshot()) {
+			self.restore(snapshot);
+		}
+	}
+
+	fn redo(&mut self) {
+		if let Some(snapshot) = self.history.redo(self.snapshot()) {
+			self.restore(snapshot);
+		}
+	}
+
+	fn to_saved(&self) -> save::SavedWorld {
+		save::SavedWorld {
+			transforms: self.transforms.iter().map(|t| save::SavedTransform { enabled: t.enabled, locked: t.locked, color_seed: t.color_seed, ..(&t.transform).into() }).collect(),
+			canvas: Some([self.width, self.height]),
+		}
+	}
+
+	/// Replace the composition with a loaded one, moving it onto this canvas if it was laid out
+	/// on one of a different size.
+	fn load_saved(&mut self, saved: save::SavedWorld) {
+		let [saved_width, saved_height] = saved.canvas.unwrap_or([self.width, self.height]);
+		let ratio = pixel::Vec2::new(self.width as f32 / saved_width as f32, self.height as f32 / saved_height as f32);
+		self.record_edit(self.snapshot());
+		let states = saved.transforms.into_iter().map(|t| {
+			let mut transform: pixel::Transform = t.into();
+			transform.position = transform.position * ratio;
+			self.next_id += 1;
+			// The seed is what's kept: its color wins over a tint edited into the file
+			if let Some(seed) = t.color_seed { transform.tint = Some(seed_tint(seed)); }
+			TransformState { id: self.next_id - 1, transform, enabled: t.enabled, locked: t.locked, color_seed: t.color_seed }
+		}).collect();
+		self.restore(states);
+	}
+
+	/// The transform under the cursor, if any.
+	fn active_transform(&self) -> Option<&pixel::Transform> {
+		self.transforms.iter().find(|t| t.controls_visible).map(|t| &t.transform)
+	}
+
+	fn has_selection(&self) -> bool {
+		self.transforms.iter().any(|t| t.selected)
+	}
+
+	fn clear_selection(&mut self) {
+		for transform in &mut self.transforms {
+			transform.selected = false;
+		}
+	}
+
+	/// Apply a keyboard nudge to every selected transform that isn't locked.
+	fn nudge_selected(&mut self, nudge: Nudge) {
+		if !self.transforms.iter().any(|t| t.selected && !t.locked) { return }
+		self.record_edit(self.snapshot());
+		for transform in self.transforms.iter_mut().filter(|t| t.selected && !t.locked) {
+			transform.nudge(nudge);
+		}
+	}
+
+	/// Scroll over the transform under the cursor, returning false when there is none so the
+	/// wheel can go to the view instead.
+	///
+	/// Only the first event of a burst over the same transform is recorded; a trackpad gesture
+	/// sends dozens.
+	fn scroll_hovered(&mut self, notches: f32, rotate: bool) -> bool {
+		let snapshot = self.snapshot();
+		let transform = match self.transforms.iter_mut().find(|t| t.controls_visible && !t.locked) {
+			Some(transform) => transform,
+			None => return false,
+		};
+		transform.scroll(notches, rotate);
+		let id = transform.id;
+		if self.scroll_burst != Some(id) {
+			self.record_edit(snapshot);
+			self.scroll_burst = Some(id);
+		}
+		self.dirty = true;
+		true
+	}
+
+	/// Delete every selected transform that isn't locked.
+	fn delete_selected(&mut self) {
+		if !self.transforms.iter().any(|t| t.selected && !t.locked) { return }
+		self.record_edit(self.snapshot());
+		self.transforms.retain(|t| !t.selected || t.locked);
+	}
+
+	fn toggle_debug_cycle(&mut self) {
+		self.debug_cycle = match self.debug_cycle {
+			Some(_) => None,
+			None => Some(CycleState::new(DEBUG_CYCLE_INTERVAL)),
+		};
+	}
+
+	fn advance_debug_cycle(&mut self, delta: Duration) {
+		let count = self.transforms.iter().filter(|t| t.enabled).count();
+		if let Some(cycle) = &mut self.debug_cycle {
+			cycle.advance(delta, count);
+		}
+	}
+
+	/// The transforms that currently contribute to the fractal: the enabled ones, narrowed down
+	/// to the soloed one while the debug cycle runs.
+	fn rendered_transforms(&self) -> Vec<pixel::Transform> {
+		// The cycle steps through the enabled transforms only, so a disabled one never blanks a step
+		let enabled: Vec<pixel::Transform> = self.transforms.iter().filter(|t| t.enabled).map(|t| t.transform).collect();
+		let count = enabled.len();
+		enabled.into_iter()
+			.enumerate()
+			.filter(|&(i, _)| self.debug_cycle.as_ref().is_none_or(|cycle| cycle.is_enabled(i, count)))
+			.map(|(_, transform)| transform)
+			.collect()
+	}
+
+	/// Move the transform under the cursor to `target(index, count)` in the front-to-back order.
+	///
+	/// Away from any transform, while the list panel is shown, its highlighted entry is moved.
+	fn reorder_active(&mut self, target: fn(usize, usize) -> usize) {
+		let i = match self.transforms.iter().position(|t| t.controls_visible && !t.locked).or_else(|| self.listed_index()) {
+			Some(i) => i,
+			None => return,
+		};
+		let j = target(i, self.transforms.len());
+		if i == j { return }
+		self.record_edit(self.snapshot());
+		let transform = self.transforms.remove(i);
+		self.transforms.insert(j, transform);
+	}
+
+	/// The list panel's highlighted entry: the selected transform, while the panel is shown and
+	/// exactly one is selected and it isn't locked.
+	fn listed_index(&self) -> Option<usize> {
+		if !self.show_list || self.selection_count() != 1 { return None }
+		self.transforms.iter().position(|t| t.selected && !t.locked)
+	}
+
+	/// Move the list panel's highlight `step` entries towards the back, or the front when
+	/// negative, selecting that transform alone so keyboard edits go to it.
+	///
+	/// Locked entries are stepped over, like clicks and the rubber band pass them by.
+	fn step_list(&mut self, step: isize) {
+		let unlocked: Vec<usize> = (0..self.transforms.len()).filter(|&i| !self.transforms[i].locked).collect();
+		if unlocked.is_empty() { return }
+		let k = match self.transforms.iter().position(|t| t.selected).and_then(|i| unlocked.iter().position(|&j| j == i)) {
+			Some(k) => (k as isize + step).clamp(0, unlocked.len() as isize - 1) as usize,
+			None => 0,
+		};
+		self.clear_selection();
+		self.transforms[unlocked[k]].selected = true;
+	}
+
+	/// The list panel's lines, front to back: each transform's place in the order, where it is
+	/// and whether it's hidden or locked, in the color its border is drawn in.
+	fn list_lines(&self) -> Vec<(String, &'static [u8; 3])> {
+		self.transforms.iter().enumerate().map(|(i, t)| {
+			let position = t.transform.position;
+			let text = format!(	"{} at {:.0} {:.0}{}{}",
+								i + 1,
+								position.x,
+								position.y,
+								if t.enabled { "" } else { " hidden" },
+								if t.locked { " locked" } else { "" });
+			let color = if t.controls_visible { &HOVERING_COLOR } else if t.selected { &SELECTED_COLOR } else { &UNHOVERABLE_COLOR };
+			(text, color)
+		}).collect()
+	}
+
+	/// Take the transform under the cursor out of the fractal, or put it back.
+	fn toggle_enabled_active(&mut self) {
+		let snapshot = self.snapshot();
+		if let Some(transform) = self.transforms.iter_mut().find(|t| t.controls_visible && !t.locked) {
+			transform.enabled = !transform.enabled;
+			self.record_edit(snapshot);
+		}
+	}
+
+	/// Lock or unlock the transform under the cursor.
+	///
+	/// The only key that acts on a locked transform, hovered through its padlock; every other
+	/// per-transform key skips it.
+	fn toggle_lock_active(&mut self) {
+		let snapshot = self.snapshot();
+		if let Some(transform) = self.transforms.iter_mut().find(|t| t.controls_visible) {
+			transform.toggle_lock();
+			self.record_edit(snapshot);
+		}
+	}
+
+	/// Mirror the transform under the cursor horizontally, or vertically when `horizontal` is false.
+	fn flip_active(&mut self, horizontal: bool) {
+		let snapshot = self.snapshot();
+		if let Some(transform) = self.transforms.iter_mut().find(|t| t.controls_visible && !t.locked) {
+			if horizontal {
+				transform.transform.scale.x = -transform.transform.scale.x;
+			} else {
+				transform.transform.scale.y = -transform.transform.scale.y;
+			}
+			self.record_edit(snapshot);
+		}
+	}
+
+	/// Toggle coverage-normalized alpha on the transform under the cursor.
+	fn toggle_alpha_normalization(&mut self) {
+		let snapshot = self.snapshot();
+		if let Some(transform) = self.transforms.iter_mut().find(|t| t.controls_visible && !t.locked) {
+			transform.transform.normalize_alpha_by_coverage = !transform.transform.normalize_alpha_by_coverage;
+			self.record_edit(snapshot);
+		}
+	}
+
+	/// Adopt a new canvas size, moving transforms (and their undo history) with the canvas so the
+	/// composition keeps its layout. Any drag in progress is dropped.
+	fn resize(&mut self, width: u32, height: u32) {
+		let ratio = pixel::Vec2::new(width as f32 / self.width as f32, height as f32 / self.height as f32);
+		for transform in &mut self.transforms {
+			transform.transform.position = transform.transform.position * ratio;
+		}
+		for snapshot in self.history.undo.iter_mut().chain(self.history.redo.iter_mut()) {
+			for state in snapshot {
+				state.transform.position = state.transform.position * ratio;
+			}
+		}
+		self.width = width;
+		self.height = height;
+		self.chaos = chaos::ChaosGame::new(width, height);
+		self.release_grabs();
+	}
+
+	/// Zoom by `steps` wheel notches, keeping the canvas point under `cursor` where it is on screen.
+	fn zoom_view(&mut self, cursor: pixel::Vec2, steps: f32) {
+		let anchor = self.view.apply_inverse(cursor);
+		let zoom = (self.view.scale.x * ZOOM_STEP.powf(steps)).clamp(MIN_ZOOM, MAX_ZOOM);
+		self.view.scale = pixel::Vec2::new(zoom, zoom);
+		self.view.position = cursor - anchor * zoom;
+	}
+
+	fn pan_view(&mut self, delta: pixel::Vec2) {
+		self.view.position += delta;
+	}
+
+	fn reset_view(&mut self) {
+		self.view = pixel::Transform::identity();
+	}
+
+	/// Grow `transforms` from the seed for `iterations` generations offscreen, with this world's
+	/// canvas size, sampling and symmetry, and return the View mode frame. More than one of
+	/// `samples` averages that many renders jittered by up to half a pixel, for anti-aliasing.
+	fn render_still(&self, clear_buffer: &[u8], transforms: &[pixel::Transform], iterations: u32, samples: usize) -> Vec<u8> {
+		let mut world = FractalWorld::new(self.width, self.height, clear_buffer.to_vec());
+		for &transform in transforms {
+			world.add_transform(transform);
 		}
+		world.sampling = self.sampling;
+		world.symmetry = self.symmetry;
+		let mut frame = vec![0u8; clear_buffer.len()];
+		if samples > 1 {
+			// Seeded, so rendering the same composition again gives the same image
+			world.render_supersampled(&mut frame, iterations, samples, 1.0, &mut StdRng::seed_from_u64(JITTER_SEED));
+		} else {
+			world.render_into(&mut frame, iterations);
+		}
+		frame
+	}
+
+	/// Run one feedback generation: the seed with every enabled transform's copy of `source`
+	/// composited over it, written to `dest`.
+	fn feedback_pass(&self, clear_buffer: &[u8], source: &mut [u8], dest: &mut [u8]) {
+		let transforms = self.rendered_transforms();
+		let transforms: Vec<&pixel::Transform> = transforms.iter().collect();
+		let feedback = fractal::Feedback {
+			width: self.width,
+			height: self.height,
+			sampling: self.sampling,
+			symmetry: self.symmetry,
+		};
+		feedback.step(&transforms, clear_buffer, source, dest);
 	}
 
-	/// Draw the `World` state to the frame buffer.
+	/// Draw the `World` state to the frame buffer, running `iterations` feedback generations.
+	///
+	/// Returns how many pixels the last generation changed from the one before it.
+	///
+	/// The canvas is presented through `view`; the feedback itself always runs at canvas scale.
 	///
 	/// Assumes the default texture format: `wgpu::TextureFormat::Rgba8UnormSrgb`
-	fn draw(&self, clear_buffer: &Box<[u8; (WIDTH * HEIGHT * 4) as usize]>, frame: &mut [u8], last_frame_buffer: &mut Box<[u8; (WIDTH * HEIGHT * 4) as usize]>, edit_mode: EditMode) {
-		frame.copy_from_slice(&clear_buffer[..]);
+	fn draw(&mut self, clear_buffer: &[u8], frame: &mut [u8], last_frame_buffer: &mut [u8], edit_mode: EditMode, view_kind: ViewKind, view: &pixel::Transform) -> usize {
+		let mut changed_pixels = 0;
+		if edit_mode == EditMode::Dual || edit_mode == EditMode::View {
+			// Ping-pong between the two buffers so extra passes don't allocate
+			for pass in 0..self.iterations {
+				if pass % 2 == 0 {
+					self.feedback_pass(clear_buffer, last_frame_buffer, frame);
+				} else {
+					self.feedback_pass(clear_buffer, frame, last_frame_buffer);
+				}
+			}
+			// Leave the result in both buffers so the next frame continues from it; the other one
+			// still holds the pass before, and the growth delta view only presents what changed
+			let result_in_frame = self.iterations % 2 == 1;
+			for (new, old) in frame.chunks_exact_mut(4).zip(last_frame_buffer.chunks_exact_mut(4)) {
+				let changed = new != old;
+				if changed { changed_pixels += 1; }
+				if result_in_frame { old.copy_from_slice(new); } else { new.copy_from_slice(old); }
+				if !changed && view_kind == ViewKind::GrowthDelta { new[0..3].copy_from_slice(&self.palette.background); }
+			}
+		} else {
+			frame.copy_from_slice(clear_buffer);
+		}
+		self.present(frame, edit_mode, view);
+		changed_pixels
+	}
+
+	/// Draw the chaos game's point cloud instead of the feedback fractal, plotting another batch
+	/// of points into the image accumulated so far.
+	///
+	/// Returns how many pixels this batch reached for the first time.
+	fn draw_chaos(&mut self, clear_buffer: &[u8], frame: &mut [u8], edit_mode: EditMode, view: &pixel::Transform) -> usize {
+		let mut newly_hit = 0;
 		if edit_mode == EditMode::Dual || edit_mode == EditMode::View {
-			let mut grid = pixel::PixelGrid(frame);
-			for (i, pixel) in last_frame_buffer.chunks_exact_mut(4).enumerate() {
-				if pixel[0] == 0xE3 { continue; }
-				let x = (i % WIDTH as usize) as f32 - WIDTH as f32 / 2.0;
-				let y = (i / WIDTH as usize) as f32 - HEIGHT as f32 / 2.0;
-				for transform in &self.transforms {
-					grid.set_pixel_transformed(pixel::Vec2::new(x, y), &transform.transform, &[pixel[0], pixel[1], pixel[2]]);
+			let transforms = self.rendered_transforms();
+			let transforms: Vec<&pixel::Transform> = transforms.iter().collect();
+			newly_hit = self.chaos.run(&transforms, CHAOS_POINTS_PER_FRAME);
+			self.chaos.grid().tonemap(frame, self.palette.background, self.palette.chaos_ink());
+		} else {
+			frame.copy_from_slice(clear_buffer);
+		}
+		self.present(frame, edit_mode, view);
+		newly_hit
+	}
+
+	/// Finish a rendered canvas for display: make it opaque, show it through `view` and draw the
+	/// edit handles over it.
+	fn present(&mut self, frame: &mut [u8], edit_mode: EditMode, view: &pixel::Transform) {
+		let (width, height) = (self.width, self.height);
+		// The mask has been carried over to last_frame_buffer; what's shown is always opaque
+		for pixel in frame.chunks_exact_mut(4) {
+			pixel[3] = 0xff;
+		}
+		if !view.is_identity() {
+			// Resample the canvas through the camera, nearest neighbour so zoomed pixels stay crisp
+			let mut canvas = std::mem::take(&mut self.view_scratch);
+			canvas.clear();
+			canvas.extend_from_slice(frame);
+			for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+				let screen = pixel::Vec2::new((i % width as usize) as f32 + 0.5, (i / width as usize) as f32 + 0.5);
+				let point = view.apply_inverse(screen);
+				if point.x < 0.0 || point.x >= width as f32 || point.y < 0.0 || point.y >= height as f32 {
+					pixel[0..3].copy_from_slice(&self.palette.background);
+				} else {
+					let source = (point.y as usize * width as usize + point.x as usize) * 4;
+					pixel.copy_from_slice(&canvas[source..source + 4]);
 				}
 			}
-			last_frame_buffer.copy_from_slice(frame);
+			self.view_scratch = canvas;
 		}
 		if edit_mode == EditMode::Dual || edit_mode == EditMode::Edit {
-			let mut grid = pixel::PixelGrid(frame);
+			let mut grid = pixel::PixelGrid::new(frame, width, height, width as usize * 4);
 			let add_color =
 						if self.hovering == Some(WorldHoverMode::Add) { &HOVERING_COLOR }
 						else { &HOVERABLE_COLOR };
 			for x in 43..57 {
 				for y in 20..80 {
 					grid.set_pixel( pixel::Vec2::new(x as f32, y as f32),
-												&add_color);
+												add_color);
 					grid.set_pixel( pixel::Vec2::new(y as f32, x as f32),
-												&add_color);
+												add_color);
+				}
+			}
+
+			// Back to front, so the transform that takes input first is also drawn on top
+			for transform in self.transforms.iter().rev() {
+				transform.draw(&mut grid, view);
+			}
+			if self.show_list {
+				for (i, (text, color)) in self.list_lines().iter().enumerate() {
+					grid.draw_text(pixel::Vec2::new(LIST_MARGIN, LIST_TOP + i as f32 * READOUT_LINE_HEIGHT), text, color);
 				}
 			}
 
-			for transform in &self.transforms {
-				transform.draw(&mut grid);
+			if let Some((start, end)) = self.rubber_band {
+				let (start, end) = (view.apply(start), view.apply(end));
+				let (min, max) = (pixel::Vec2::new(start.x.min(end.x), start.y.min(end.y)), pixel::Vec2::new(start.x.max(end.x), start.y.max(end.y)));
+				for x in min.x as i32..=max.x as i32 {
+					grid.set_pixel(pixel::Vec2::new(x as f32, min.y), &SELECTED_COLOR);
+					grid.set_pixel(pixel::Vec2::new(x as f32, max.y), &SELECTED_COLOR);
+				}
+				for y in min.y as i32..=max.y as i32 {
+					grid.set_pixel(pixel::Vec2::new(min.x, y as f32), &SELECTED_COLOR);
+					grid.set_pixel(pixel::Vec2::new(max.x, y as f32), &SELECTED_COLOR);
+				}
 			}
 		}
 	}
 }
 
+/// Clamp a scale component's magnitude into the handle's range, keeping its sign so flips survive.
+fn clamp_scale(scale: f32) -> f32 {
+	scale.signum() * scale.abs().clamp(MIN_SCALE, MAX_SCALE)
+}
+
+/// The contraction ratio as the readout shows it, and the color it and the meter are drawn in: red
+/// once the transform stops shrinking.
+fn contraction_readout(transform: &pixel::Transform) -> (String, &'static [u8; 3]) {
+	let contraction = transform.max_scale_factor();
+	let color = if contraction < 1.0 { &CONTRACTIVE_COLOR } else { &EXPANSIVE_COLOR };
+	(format!("contraction {:.2}", contraction), color)
+}
+
+/// Wrap `angle` into (-π, π].
+fn normalize_angle(angle: f32) -> f32 {
+	let angle = angle.rem_euclid(std::f32::consts::TAU);
+	if angle > std::f32::consts::PI { angle - std::f32::consts::TAU } else { angle }
+}
+
+/// Direction of `offset` in radians, clockwise from straight up on screen; the rotation that
+/// points a transform's top at it.
+fn cursor_angle(offset: pixel::Vec2) -> f32 {
+	offset.x.atan2(-offset.y)
+}
+
 #[derive(Eq, PartialEq, Copy, Clone)]
 enum Hoverables {
 	Rotate,
 	Translate,
 	Scale,
+	ScaleX,
+	ScaleY,
 	Alpha,
 	Delete,
+	Duplicate,
+	Tint,
+	Lock,
+}
+
+/// What a transform did with the mouse input it was given.
+#[derive(Eq, PartialEq, Copy, Clone)]
+enum MouseResponse {
+	/// The cursor is outside the transform, so transforms behind it get the input.
+	Ignored,
+	/// The transform handled the input.
+	Consumed,
+	/// The transform handled the input and wants the world to add a copy of it.
+	Duplicate,
+	/// The transform handled the input and wants its tint advanced through the palette.
+	CycleTint,
+	/// The transform's lock was toggled; it stays where it is in the order.
+	ToggleLock,
+}
+
+/// A fine adjustment of the selected transform from the keyboard.
+#[derive(Copy, Clone)]
+enum Nudge {
+	Translate(pixel::Vec2),
+	/// Radians, clockwise on screen.
+	Rotate(f32),
+	/// Added to the magnitude of both scale axes.
+	Scale(f32),
+	Alpha(f32),
+	Weight(f32),
+}
+
+const NUDGE_SCALE_STEP: f32 = 0.01;
+const NUDGE_ALPHA_STEP: f32 = 8.0;
+const NUDGE_WEIGHT_STEP: f32 = 0.1;
+/// Scale factor and rotation per scroll wheel notch over a transform.
+const SCROLL_SCALE_STEP: f32 = 1.05;
+const SCROLL_ROTATION_STEP: f32 = 2.0 * std::f32::consts::PI / 180.0;
+
+/// The nudge bound to a key pressed this frame: arrows translate, Q/E rotate, +/- scale, [/]
+/// alpha and ;/' weight. Shift makes translation and rotation coarser.
+fn pressed_nudge(input: &WinitInputHelper) -> Option<Nudge> {
+	let step = if input.held_shift() { 10.0 } else { 1.0 };
+	let angle = if input.held_shift() { 15f32.to_radians() } else { 1f32.to_radians() };
+	let pressed = |keys: &[VirtualKeyCode]| keys.iter().any(|&key| input.key_pressed(key));
+	if pressed(&[VirtualKeyCode::Left]) {
+		Some(Nudge::Translate(pixel::Vec2::new(-step, 0.0)))
+	} else if pressed(&[VirtualKeyCode::Right]) {
+		Some(Nudge::Translate(pixel::Vec2::new(step, 0.0)))
+	} else if pressed(&[VirtualKeyCode::Up]) {
+		Some(Nudge::Translate(pixel::Vec2::new(0.0, -step)))
+	} else if pressed(&[VirtualKeyCode::Down]) {
+		Some(Nudge::Translate(pixel::Vec2::new(0.0, step)))
+	} else if pressed(&[VirtualKeyCode::Q]) {
+		Some(Nudge::Rotate(-angle))
+	} else if pressed(&[VirtualKeyCode::E]) {
+		Some(Nudge::Rotate(angle))
+	} else if pressed(&[VirtualKeyCode::Equals, VirtualKeyCode::Plus, VirtualKeyCode::NumpadAdd]) {
+		Some(Nudge::Scale(NUDGE_SCALE_STEP))
+	} else if pressed(&[VirtualKeyCode::Minus, VirtualKeyCode::NumpadSubtract]) {
+		Some(Nudge::Scale(-NUDGE_SCALE_STEP))
+	} else if pressed(&[VirtualKeyCode::LBracket]) {
+		Some(Nudge::Alpha(-NUDGE_ALPHA_STEP))
+	} else if pressed(&[VirtualKeyCode::RBracket]) {
+		Some(Nudge::Alpha(NUDGE_ALPHA_STEP))
+	} else if pressed(&[VirtualKeyCode::Semicolon]) {
+		Some(Nudge::Weight(-NUDGE_WEIGHT_STEP))
+	} else if pressed(&[VirtualKeyCode::Apostrophe]) {
+		Some(Nudge::Weight(NUDGE_WEIGHT_STEP))
+	} else {
+		None
+	}
+}
+
+/// Increments grabs snap to while the snap modifier is held.
+const SNAP_ROTATION: f32 = std::f32::consts::PI / 12.0;
+const SNAP_SCALE: f32 = 0.05;
+const SNAP_POSITION: f32 = 10.0;
+
+/// Screen-space offset of a duplicated transform from its original.
+const DUPLICATE_OFFSET: f32 = 30.0;
+
+/// One transform as undo, redo and keyframes keep it: the transform and the editor state that's
+/// undone along with it.
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct TransformState {
+	/// Follows the transform through reorders, so state kept outside snapshots can find it again.
+	id: u64,
+	transform: pixel::Transform,
+	enabled: bool,
+	locked: bool,
+	color_seed: Option<u64>,
 }
 
 struct ScreenTransform {
+	id: u64,
 	transform: pixel::Transform,
 	controls_visible: bool,
 	hovering: Option<Hoverables>,
 	grabbing: Option<Hoverables>,
-	scale_start: Option<pixel::Vec2>,
+	/// Cursor position and scale when the scale handle was pressed.
+	scale_start: Option<(pixel::Vec2, pixel::Vec2)>,
+	/// Whether the current grab is snapping, as of the last input.
+	snapping: bool,
+	/// Cursor angle around the center minus the rotation, when the rotate handle was pressed.
+	rotate_offset: f32,
+	/// Added to the value the cursor implies for the current grab; see `grab_value`.
+	grab_offset: f32,
+	grab_position_offset: pixel::Vec2,
 	dead: bool,
+	/// Whether the transform contributes to the fractal; the debug cycle solos within the enabled
+	/// ones without touching this.
+	enabled: bool,
+	/// Whether keyboard nudges apply to this transform; set by the last click or rubber band.
+	selected: bool,
+	/// Whether the handles are off limits, so input passes through to the transforms behind.
+	locked: bool,
+	/// What the tint was rolled from, if it was; see `seed_tint`.
+	color_seed: Option<u64>,
 }
 
 const UNHOVERABLE_COLOR: [u8; 3] = [0x13, 0x1B, 0x23];
 const HOVERABLE_COLOR: [u8; 3] = [0xA6, 0x26, 0x39];
 const HOVERING_COLOR: [u8; 3] = [0xDB, 0x32, 0x4D];
 const CLICKING_COLOR: [u8; 3] = [0x85, 0x1E, 0x2E];
+const SELECTED_COLOR: [u8; 3] = [0x3D, 0x8B, 0xFF];
+/// Screen pixels between a transform's center and the top of its readout.
+const READOUT_OFFSET: f32 = 50.0;
+/// Screen pixels between the tops of consecutive readout lines.
+const READOUT_LINE_HEIGHT: f32 = 18.0;
+/// Lowest alpha the alpha handle allows, so a transform never fades out of sight entirely.
+const MIN_ALPHA: u8 = 0x20;
+const ALPHA_BAR_WIDTH: f32 = 30.0;
+const ALPHA_BAR_HALF_LENGTH: f32 = 150.0;
+
+/// Tints the swatch handle cycles through, after starting from no tint.
+const TINT_PALETTE: [[u8; 3]; 8] = [
+	[0xE6, 0x39, 0x46],
+	[0xF4, 0xA2, 0x61],
+	[0xE9, 0xC4, 0x6A],
+	[0x2A, 0x9D, 0x8F],
+	[0x45, 0x7B, 0x9D],
+	[0x6D, 0x59, 0x7A],
+	[0xB5, 0x65, 0x76],
+	[0x26, 0x46, 0x53],
+];
+
+/// Seed of `World::color_rng` when `--color-seed` isn't given.
+const DEFAULT_COLOR_SEED: u64 = 0xC010;
+
+/// The tint a color seed stands for: a fully saturated hue at a random brightness.
+///
+/// Hashed with SplitMix64 rather than drawn from a `rand` generator, whose output may change
+/// between versions, so a saved seed gives the same color in every build.
+fn seed_tint(seed: u64) -> [u8; 3] {
+	let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+	z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+	z ^= z >> 31;
+	let hue = (z & 0xFFFF) as f32 / 65536.0 * 6.0;
+	let value = 0.6 + ((z >> 16) & 0xFF) as f32 / 255.0 * 0.4;
+	// The sixth of the hue wheel decides which channel is full, which is rising or falling and which is off
+	let rising = hue.fract();
+	let (r, g, b) = match hue as u32 {
+		0 => (1.0, rising, 0.0),
+		1 => (1.0 - rising, 1.0, 0.0),
+		2 => (0.0, 1.0, rising),
+		3 => (0.0, 1.0 - rising, 1.0),
+		4 => (rising, 0.0, 1.0),
+		_ => (1.0, 0.0, 1.0 - rising),
+	};
+	[r, g, b].map(|channel: f32| (channel * value * 255.0).round() as u8)
+}
+
+const FIXED_POINT_COLOR: [u8; 3] = [0x1E, 0x5A, 0xA6];
+const CONTRACTIVE_COLOR: [u8; 3] = [0x23, 0xA9, 0x50];
+const EXPANSIVE_COLOR: [u8; 3] = [0xD9, 0x2B, 0x1C];
 
 impl ScreenTransform {
-	fn draw(&self, grid: &mut pixel::PixelGrid) {
-		let width = WIDTH as f32;
-		let height = HEIGHT as f32;
+	fn new(id: u64, transform: pixel::Transform) -> Self {
+		Self {
+			id,
+			transform,
+			controls_visible: false,
+			hovering: None,
+			grabbing: None,
+			scale_start: None,
+			snapping: false,
+			rotate_offset: 0.0,
+			grab_offset: 0.0,
+			grab_position_offset: pixel::Vec2::new(0.0, 0.0),
+			dead: false,
+			enabled: true,
+			selected: false,
+			locked: false,
+			color_seed: None,
+		}
+	}
+
+	fn from_state(state: TransformState) -> Self {
+		Self { enabled: state.enabled, locked: state.locked, color_seed: state.color_seed, ..Self::new(state.id, state.transform) }
+	}
 
+	fn state(&self) -> TransformState {
+		TransformState { id: self.id, transform: self.transform, enabled: self.enabled, locked: self.locked, color_seed: self.color_seed }
+	}
 
+	/// Color this transform with `seed`'s tint, keeping the seed.
+	fn take_color_seed(&mut self, seed: u64) {
+		self.color_seed = Some(seed);
+		self.transform.tint = Some(seed_tint(seed));
+	}
+
+	/// The padlock handle, shut when locked and with its shackle raised when not.
+	fn draw_lock(&self, grid: &mut pixel::PixelGrid, transform: &pixel::Transform) {
+		let color =
+			if self.hovering == Some(Hoverables::Lock) { &HOVERING_COLOR }
+			else { &HOVERABLE_COLOR };
+		let origin = pixel::Vec2::new(	-(grid.width() as f32) / 2.0 + 15.0 / self.transform.scale.x.abs(),
+										-(grid.height() as f32) / 2.0 + 65.0 + 15.0 / self.transform.scale.y.abs());
+		let shackle_bottom = if self.locked { 24.0 } else { 16.0 };
+		for x in 0..50 {
+			for y in 0..50 {
+				let (x, y) = (x as f32, y as f32);
+				let shackle = (pixel::Vec2::new(x, y) - pixel::Vec2::new(25.0, shackle_bottom)).magnitude();
+				let body = (5.0..45.0).contains(&x) && y >= 24.0;
+				let ring = (10.0..16.0).contains(&shackle) && y <= shackle_bottom;
+				let post = (10.0..16.0).contains(&(x - 25.0).abs()) && (shackle_bottom..24.0).contains(&y);
+				if body || ring || post {
+					grid.set_pixel_transformed( origin + pixel::Vec2::new(x, y),
+												transform,
+												color);
+				}
+			}
+		}
+	}
+
+	/// Rotation, scale, alpha and weight as shown next to the handles, e.g.
+	/// `rot 34.2° scale 0.55 a 94% w 1.0`.
+	fn readout(&self) -> String {
+		let transform = &self.transform;
+		let scale =
+			if transform.scale.x == transform.scale.y { format!("{:.2}", transform.scale.x) }
+			else { format!("{:.2}x{:.2}", transform.scale.x, transform.scale.y) };
+		format!("rot {:.1}° scale {} a {:.0}% w {:.1}",
+				normalize_angle(transform.rotation).to_degrees(),
+				scale,
+				transform.alpha as f32 / 255.0 * 100.0,
+				transform.weight)
+	}
+
+	/// Draw the outline and handles as seen through the viewport camera `view`.
+	fn draw(&self, grid: &mut pixel::PixelGrid, view: &pixel::Transform) {
+		let width = grid.width() as f32;
+		let height = grid.height() as f32;
+		let transform = self.transform.viewed_through(view);
+		let border_color = if self.selected && !self.locked { &SELECTED_COLOR } else { &UNHOVERABLE_COLOR };
 
 		// let precision_scale = 1.0;
 		let mut x = -width / 2.0;
 		while x < width / 2.0 {
 			for line_width in 0..10 {
-				grid.set_pixel_transformed( pixel::Vec2::new(x, -height / 2.0 + (line_width as f32) / self.transform.scale),
-											&self.transform,
-											&UNHOVERABLE_COLOR);
-				grid.set_pixel_transformed( pixel::Vec2::new(x, height / 2.0 - (line_width as f32) / self.transform.scale),
-											&self.transform,
-											&UNHOVERABLE_COLOR);
+				grid.set_pixel_transformed( pixel::Vec2::new(x, -height / 2.0 + (line_width as f32) / transform.scale.y.abs()),
+											&transform,
+											border_color);
+				grid.set_pixel_transformed( pixel::Vec2::new(x, height / 2.0 - (line_width as f32) / transform.scale.y.abs()),
+											&transform,
+											border_color);
 			}
-			x += self.transform.scale;
+			x += 1.0 / transform.scale.x.abs();
 		}
 		let mut y = -height / 2.0;
 		while y < height / 2.0 {
 			for line_width in 0..10 {
-				grid.set_pixel_transformed( pixel::Vec2::new(-width / 2.0 + (line_width as f32) / self.transform.scale, y),
-											&self.transform,
-											&UNHOVERABLE_COLOR);
-				grid.set_pixel_transformed( pixel::Vec2::new(width / 2.0 - (line_width as f32) / self.transform.scale, y),
-											&self.transform,
-											&UNHOVERABLE_COLOR);
+				grid.set_pixel_transformed( pixel::Vec2::new(-width / 2.0 + (line_width as f32) / transform.scale.x.abs(), y),
+											&transform,
+											border_color);
+				grid.set_pixel_transformed( pixel::Vec2::new(width / 2.0 - (line_width as f32) / transform.scale.x.abs(), y),
+											&transform,
+											border_color);
 			}
-			y += self.transform.scale;
+			y += 1.0 / transform.scale.y.abs();
 		}
 
-		if !self.controls_visible { return }
+		self.draw_fixed_point(grid, view);
+
+		// A locked transform always shows its padlock, and nothing else
+		if self.locked || self.controls_visible { self.draw_lock(grid, &transform); }
+		if self.locked || !self.controls_visible { return }
 
 		let rotate_color =
 			if self.grabbing == Some(Hoverables::Rotate) { &CLICKING_COLOR }
 			else if self.hovering == Some(Hoverables::Rotate) { &HOVERING_COLOR }
 			else { &HOVERABLE_COLOR };
-		for x in -75..75 {
-			let outer_arc = (100.0*100.0 - x as f32 * x as f32).sqrt() as i16;
-			let inner_arc = (75.0*75.0 - x as f32 * x as f32).sqrt() as i16;
-			for y in inner_arc.max(50)..outer_arc {
-				grid.set_pixel_transformed( pixel::Vec2::new(x as f32, -y as f32 - height / 2.0 + 100.0 + 15.0 / self.transform.scale),
-											&self.transform,
-											&rotate_color);
-			}
-		}
+		// Centered on wherever local "up" lands on screen; the arc spans the same ±48.6° the handle always has.
+		// Its radii run along local y, the axis `over_rotate` measures the handle's band on
+		let arc_center = transform.apply(pixel::Vec2::new(0.0, -height / 2.0 + 100.0 + 15.0 / self.transform.scale.y.abs()));
+		let up = (pixel::Vec2::new(0.0, -1.0) * transform.scale).rotate(transform.rotation);
+		let arc_direction = up.y.atan2(up.x);
+		let arc_half_span = 0.75f32.asin();
+		grid.fill_arc(	arc_center,
+						75.0 * transform.scale.y.abs(),
+						100.0 * transform.scale.y.abs(),
+						arc_direction - arc_half_span,
+						arc_direction + arc_half_span,
+						rotate_color,
+						true);
 		for x in 40..85 {
 			for y in 40..x {
-				grid.set_pixel_transformed( pixel::Vec2::new(x as f32, -y as f32 - height / 2.0 + 100.0 + 15.0 / self.transform.scale),
-											&self.transform,
-											&rotate_color);
-				grid.set_pixel_transformed( pixel::Vec2::new(-x as f32, -y as f32 - height / 2.0 + 100.0 + 15.0 / self.transform.scale),
-											&self.transform,
-											&rotate_color);
+				grid.set_pixel_transformed( pixel::Vec2::new(x as f32, -y as f32 - height / 2.0 + 100.0 + 15.0 / self.transform.scale.y.abs()),
+											&transform,
+											rotate_color);
+				grid.set_pixel_transformed( pixel::Vec2::new(-x as f32, -y as f32 - height / 2.0 + 100.0 + 15.0 / self.transform.scale.y.abs()),
+											&transform,
+											rotate_color);
 			}
 		}
 
@@ -348,27 +1822,27 @@ impl ScreenTransform {
 		for x in -10..10 {
 			for y in -40..40 {
 				grid.set_pixel_transformed( pixel::Vec2::new(x as f32, y as f32),
-											&self.transform,
-											&translate_color);
+											&transform,
+											translate_color);
 				grid.set_pixel_transformed( pixel::Vec2::new(y as f32, x as f32),
-											&self.transform,
-											&translate_color);
+											&transform,
+											translate_color);
 			}
 		}
 		for x in -25i32..25 {
 			for y in 0..(25 - x.abs()) {
 				grid.set_pixel_transformed( pixel::Vec2::new(x as f32, (y + 40) as f32),
-											&self.transform,
-											&translate_color);
+											&transform,
+											translate_color);
 				grid.set_pixel_transformed( pixel::Vec2::new(x as f32, -(y + 40) as f32),
-											&self.transform,
-											&translate_color);
+											&transform,
+											translate_color);
 				grid.set_pixel_transformed( pixel::Vec2::new((y + 40) as f32, x as f32),
-											&self.transform,
-											&translate_color);
+											&transform,
+											translate_color);
 				grid.set_pixel_transformed( pixel::Vec2::new(-(y + 40) as f32, x as f32),
-											&self.transform,
-											&translate_color);
+											&transform,
+											translate_color);
 			}
 		}
 
@@ -378,12 +1852,64 @@ impl ScreenTransform {
 			else { &HOVERABLE_COLOR };
 		for x in 0..80 {
 			for y in 60..80 {
-				grid.set_pixel_transformed( pixel::Vec2::new(x as f32 + width / 2.0 - 80.0 - 15.0 / self.transform.scale, y as f32 + height / 2.0 - 80.0 - 15.0 / self.transform.scale),
-											&self.transform,
-											&scale_color);
-				grid.set_pixel_transformed( pixel::Vec2::new(y as f32 + width / 2.0 - 80.0 - 15.0 / self.transform.scale, x as f32 + height / 2.0 - 80.0 - 15.0 / self.transform.scale),
-											&self.transform,
-											&scale_color);
+				grid.set_pixel_transformed( pixel::Vec2::new(x as f32 + width / 2.0 - 80.0 - 15.0 / self.transform.scale.x.abs(), y as f32 + height / 2.0 - 80.0 - 15.0 / self.transform.scale.y.abs()),
+											&transform,
+											scale_color);
+				grid.set_pixel_transformed( pixel::Vec2::new(y as f32 + width / 2.0 - 80.0 - 15.0 / self.transform.scale.x.abs(), x as f32 + height / 2.0 - 80.0 - 15.0 / self.transform.scale.y.abs()),
+											&transform,
+											scale_color);
+			}
+		}
+
+		// Per-axis scale handles on the right and bottom edges
+		let scale_x_color =
+			if self.grabbing == Some(Hoverables::ScaleX) { &CLICKING_COLOR }
+			else if self.hovering == Some(Hoverables::ScaleX) { &HOVERING_COLOR }
+			else { &HOVERABLE_COLOR };
+		let scale_y_color =
+			if self.grabbing == Some(Hoverables::ScaleY) { &CLICKING_COLOR }
+			else if self.hovering == Some(Hoverables::ScaleY) { &HOVERING_COLOR }
+			else { &HOVERABLE_COLOR };
+		for x in 0..30 {
+			for y in -40..40 {
+				grid.set_pixel_transformed( pixel::Vec2::new(x as f32 + width / 2.0 - 35.0 - 15.0 / self.transform.scale.x.abs(), y as f32),
+											&transform,
+											scale_x_color);
+				grid.set_pixel_transformed( pixel::Vec2::new(y as f32, x as f32 + height / 2.0 - 35.0 - 15.0 / self.transform.scale.y.abs()),
+											&transform,
+											scale_y_color);
+			}
+		}
+
+		// Alpha bar along the left edge, filled from the bottom up to the current level
+		let alpha_color =
+			if self.grabbing == Some(Hoverables::Alpha) { &CLICKING_COLOR }
+			else if self.hovering == Some(Hoverables::Alpha) { &HOVERING_COLOR }
+			else { &HOVERABLE_COLOR };
+		let alpha_level = (self.transform.alpha.max(MIN_ALPHA) - MIN_ALPHA) as f32 / (0xff - MIN_ALPHA) as f32;
+		let alpha_fill_top = ALPHA_BAR_HALF_LENGTH - alpha_level * 2.0 * ALPHA_BAR_HALF_LENGTH;
+		for y in -ALPHA_BAR_HALF_LENGTH as i32..ALPHA_BAR_HALF_LENGTH as i32 {
+			let color =
+				if y as f32 >= alpha_fill_top { alpha_color }
+				else { &UNHOVERABLE_COLOR };
+			for x in 0..ALPHA_BAR_WIDTH as i32 {
+				grid.set_pixel_transformed( pixel::Vec2::new(x as f32 - width / 2.0 + 15.0 / self.transform.scale.x.abs(), y as f32),
+											&transform,
+											color);
+			}
+		}
+
+		// Contraction meter: filled in proportion to the ratio, red once the transform stops shrinking
+		let contraction = self.transform.max_scale_factor();
+		let (contraction_label, contraction_color) = contraction_readout(&self.transform);
+		for x in -100..100 {
+			let color =
+				if (x + 100) as f32 <= contraction.min(1.0) * 200.0 { contraction_color }
+				else { &UNHOVERABLE_COLOR };
+			for y in 80..95 {
+				grid.set_pixel_transformed( pixel::Vec2::new(x as f32, y as f32),
+											&transform,
+											color);
 			}
 		}
 
@@ -395,113 +1921,1024 @@ impl ScreenTransform {
 			for y in -40..40 {
 				let x = x as f32;
 				let y = y as f32;
-				grid.set_pixel_transformed( pixel::Vec2::new((x + y) / 2.0 - width / 2.0 + 15.0 / self.transform.scale,
-															 (x - y) / 2.0 - height / 2.0 + 15.0 / self.transform.scale),
-											&self.transform,
-											&delete_color);
-				grid.set_pixel_transformed( pixel::Vec2::new((y + x) / 2.0 - width / 2.0 + 15.0 / self.transform.scale,
-															 (y - x) / 2.0 + 40.0 - height / 2.0 + 15.0 / self.transform.scale),
-											&self.transform,
-											&delete_color);
+				grid.set_pixel_transformed( pixel::Vec2::new((x + y) / 2.0 - width / 2.0 + 15.0 / self.transform.scale.x.abs(),
+															 (x - y) / 2.0 - height / 2.0 + 15.0 / self.transform.scale.y.abs()),
+											&transform,
+											delete_color);
+				grid.set_pixel_transformed( pixel::Vec2::new((y + x) / 2.0 - width / 2.0 + 15.0 / self.transform.scale.x.abs(),
+															 (y - x) / 2.0 + 40.0 - height / 2.0 + 15.0 / self.transform.scale.y.abs()),
+											&transform,
+											delete_color);
 			}
 		}
 
+		// Tint swatch in the top right corner: the current tint inside a hoverable frame
+		let tint_frame_color =
+			if self.hovering == Some(Hoverables::Tint) { &HOVERING_COLOR }
+			else { &HOVERABLE_COLOR };
+		let tint_fill_color = self.transform.tint.unwrap_or(UNHOVERABLE_COLOR);
+		for x in 0..50 {
+			for y in 0..50 {
+				let color =
+					if !(6..44).contains(&x) || !(6..44).contains(&y) { tint_frame_color }
+					else { &tint_fill_color };
+				grid.set_pixel_transformed( pixel::Vec2::new(x as f32 + width / 2.0 - 65.0 - 15.0 / self.transform.scale.x.abs(),
+															 y as f32 - height / 2.0 + 15.0 / self.transform.scale.y.abs()),
+											&transform,
+											color);
+			}
+		}
+
+		// Duplicate: an outlined square with a filled copy overlapping it, right of the delete cross
+		let duplicate_color =
+			if self.grabbing == Some(Hoverables::Duplicate) { &CLICKING_COLOR }
+			else if self.hovering == Some(Hoverables::Duplicate) { &HOVERING_COLOR }
+			else { &HOVERABLE_COLOR };
+		let duplicate_origin = pixel::Vec2::new(-width / 2.0 + 65.0 + 15.0 / self.transform.scale.x.abs(),
+												-height / 2.0 + 15.0 / self.transform.scale.y.abs());
+		for x in 0..35 {
+			for y in 0..35 {
+				if !(6..29).contains(&x) || !(6..29).contains(&y) {
+					grid.set_pixel_transformed( duplicate_origin + pixel::Vec2::new(x as f32, y as f32),
+												&transform,
+												duplicate_color);
+				}
+				grid.set_pixel_transformed( duplicate_origin + pixel::Vec2::new(x as f32 + 15.0, y as f32 + 15.0),
+											&transform,
+											duplicate_color);
+			}
+		}
+
+		// Exact values, upright in screen space just below the center so they read at any rotation
+		let readout = self.readout();
+		grid.draw_text( transform.position + pixel::Vec2::new(-pixel::text_width(&readout) / 2.0, READOUT_OFFSET),
+						&readout,
+						&UNHOVERABLE_COLOR);
+		grid.draw_text( transform.position + pixel::Vec2::new(-pixel::text_width(&contraction_label) / 2.0, READOUT_OFFSET + READOUT_LINE_HEIGHT),
+						&contraction_label,
+						contraction_color);
+	}
+
+	fn toggle_lock(&mut self) {
+		self.locked = !self.locked;
+		self.selected = false;
+		self.grabbing = None;
+		self.hovering = None;
+	}
+
+	/// Whether `local_pos` is over the padlock, which sits below the delete cross.
+	fn over_lock(&self, local_pos: pixel::Vec2, width: f32, height: f32) -> bool {
+		local_pos.x > -width / 2.0 + 15.0 / self.transform.scale.x.abs() &&
+		local_pos.x < -width / 2.0 + 50.0 + 15.0 / self.transform.scale.x.abs() &&
+		local_pos.y > -height / 2.0 + 65.0 + 15.0 / self.transform.scale.y.abs() &&
+		local_pos.y < -height / 2.0 + 115.0 + 15.0 / self.transform.scale.y.abs()
+	}
+
+	/// Whether `local_pos` is over the rotate arc and its arrowheads, centered at the top.
+	fn over_rotate(&self, local_pos: pixel::Vec2, height: f32) -> bool {
+		local_pos.x > -85.0 &&
+		local_pos.x < 85.0 &&
+		local_pos.y > -height / 2.0 + 15.0 / self.transform.scale.y.abs() &&
+		local_pos.y < -height / 2.0 + 70.0 + 15.0 / self.transform.scale.y.abs()
+	}
+
+	fn start_grab(&mut self, handle: Hoverables) {
+		self.grabbing = Some(handle);
+		self.grab_offset = 0.0;
+		self.grab_position_offset = pixel::Vec2::new(0.0, 0.0);
+		self.snapping = false;
+	}
+
+	/// The value a grab sets, given the `free` one the cursor implies and the `current` one.
+	///
+	/// While snapping the value is rounded to multiples of `step`. When snapping is let go, the
+	/// difference from the cursor is kept as an offset so the drag carries on from the snapped value.
+	fn grab_value(&mut self, free: f32, current: f32, step: f32, released_snap: bool) -> f32 {
+		if released_snap { self.grab_offset = current - free; }
+		let value = free + self.grab_offset;
+		if self.snapping { (value / step).round() * step } else { value }
+	}
+
+	/// Set the scale, clamping each axis into the handle's range.
+	fn set_scale(&mut self, scale: pixel::Vec2) {
+		self.transform.scale = pixel::Vec2::new(clamp_scale(scale.x), clamp_scale(scale.y));
+	}
+
+	/// Set the alpha, clamped so the transform never fades out entirely.
+	fn set_alpha(&mut self, alpha: f32) {
+		self.transform.alpha = alpha.round().clamp(MIN_ALPHA as f32, 0xff as f32) as u8;
 	}
 
-	fn mouse_input(&mut self, pos: pixel::Vec2, mouse_state: MouseClickState) -> bool {
-		let width = WIDTH as f32;
-		let height = HEIGHT as f32;
+	fn nudge(&mut self, nudge: Nudge) {
+		match nudge {
+			Nudge::Translate(delta) => self.transform.position += delta,
+			Nudge::Rotate(angle) => self.transform.rotation = normalize_angle(self.transform.rotation + angle),
+			Nudge::Scale(delta) => {
+				let scale = self.transform.scale;
+				self.set_scale(pixel::Vec2::new(scale.x + scale.x.signum() * delta, scale.y + scale.y.signum() * delta));
+			},
+			Nudge::Alpha(delta) => self.set_alpha(self.transform.alpha as f32 + delta),
+			// Kept on whole steps so stepping down lands on exactly 0, which leaves the transform out
+			Nudge::Weight(delta) => {
+				let weight = ((self.transform.weight + delta) / NUDGE_WEIGHT_STEP).round() * NUDGE_WEIGHT_STEP;
+				self.transform.weight = weight.clamp(0.0, 1.0);
+			},
+		}
+	}
+
+	/// Apply `notches` of scroll wheel: scale by `SCROLL_SCALE_STEP` per notch, or with `rotate`
+	/// turn by `SCROLL_ROTATION_STEP`.
+	fn scroll(&mut self, notches: f32, rotate: bool) {
+		if rotate {
+			self.transform.rotation = normalize_angle(self.transform.rotation + notches * SCROLL_ROTATION_STEP);
+		} else {
+			self.set_scale(self.transform.scale * SCROLL_SCALE_STEP.powf(notches));
+		}
+	}
+
+	fn draw_fixed_point(&self, grid: &mut pixel::PixelGrid, view: &pixel::Transform) {
+		let fixed_point = match self.transform.fixed_point(grid.width(), grid.height()) {
+			Some(fixed_point) => view.apply(fixed_point),
+			None => return,
+		};
+		for offset in -6..=6 {
+			for line_width in -1..=1 {
+				for (x, y) in [(offset, line_width), (line_width, offset)] {
+					grid.set_pixel(fixed_point + pixel::Vec2::new(x as f32, y as f32), &FIXED_POINT_COLOR);
+				}
+			}
+		}
+	}
+
+	/// Handle the cursor at `pos`; with `snap` held, grabs snap rotation, scale and position.
+	fn mouse_input(&mut self, pos: pixel::Vec2, mouse_state: MouseClickState, snap: bool, width: u32, height: u32) -> MouseResponse {
+		let width = width as f32;
+		let height = height as f32;
 		let local_pos = self.transform.apply_inverse(pos);
 
 		if let Some(grabbing) = self.grabbing {
 			self.hovering = None;
+			let released_snap = self.snapping && !snap;
+			self.snapping = snap;
 			match grabbing {
 				Hoverables::Rotate => {
-					let pos = pos - self.transform.position;
-					let angle = if pos.y >= 0.0 {
-						-(pos.x / pos.y).atan() + std::f32::consts::PI
-					} else {
-						-(pos.x / pos.y).atan()
-					};
-					self.transform.rotation = angle;
+					// Relative to the press, unwrapped to the turn nearest the current rotation so crossing
+					// atan2's branch cut never spins the transform
+					let reference = self.transform.rotation - self.grab_offset;
+					let free = reference + normalize_angle(cursor_angle(pos - self.transform.position) - self.rotate_offset - reference);
+					let rotation = self.grab_value(free, self.transform.rotation, SNAP_ROTATION, released_snap);
+					self.transform.rotation = normalize_angle(rotation);
 				},
 				Hoverables::Translate => {
-					self.transform.position = pos;
+					if released_snap { self.grab_position_offset = self.transform.position - pos; }
+					let position = pos + self.grab_position_offset;
+					self.transform.position = if snap {
+						pixel::Vec2::new(	(position.x / SNAP_POSITION).round() * SNAP_POSITION,
+											(position.y / SNAP_POSITION).round() * SNAP_POSITION)
+					} else { position };
 				},
 				Hoverables::Scale => {
-					let current_distance = pos - self.transform.position;
-					self.transform.scale = current_distance.magnitude() * WIDTH as f32 / HEIGHT as f32 / 540.0;
-					self.transform.scale = self.transform.scale.max(0.1).min(1.0);
+					// Uniform and relative to the press: the scale grows with the cursor's distance from the
+					// center, keeping the x:y ratio it started with
+					if let Some((start_pos, start_scale)) = self.scale_start {
+						let start_distance = (start_pos - self.transform.position).magnitude();
+						let ratio =
+							if start_distance > f32::EPSILON { (pos - self.transform.position).magnitude() / start_distance }
+							else { 1.0 };
+						let start_largest = start_scale.x.abs().max(start_scale.y.abs());
+						let largest = self.grab_value(start_largest * ratio, self.transform.max_scale_factor(), SNAP_SCALE, released_snap);
+						self.set_scale(start_scale * (largest / start_largest));
+					}
 				},
-				_ => (),
-			}
-			match mouse_state {
-				MouseClickState::Released => {
-					self.grabbing = None;
+				Hoverables::ScaleX => {
+					// The edge handle's center sits at w/2 - 20 - 15/|s| locally, i.e. |s|*(w/2 - 20) - 15 on
+					// screen, on the side the flip puts it
+					let offset = (pos - self.transform.position).rotate(-self.transform.rotation);
+					let sign = self.transform.scale.x.signum();
+					let magnitude = self.grab_value((offset.x * sign + 15.0) / (width / 2.0 - 20.0), self.transform.scale.x.abs(), SNAP_SCALE, released_snap);
+					self.set_scale(pixel::Vec2::new(sign * magnitude, self.transform.scale.y));
+				},
+				Hoverables::ScaleY => {
+					let offset = (pos - self.transform.position).rotate(-self.transform.rotation);
+					let sign = self.transform.scale.y.signum();
+					let magnitude = self.grab_value((offset.y * sign + 15.0) / (height / 2.0 - 20.0), self.transform.scale.y.abs(), SNAP_SCALE, released_snap);
+					self.set_scale(pixel::Vec2::new(self.transform.scale.x, sign * magnitude));
+				},
+				Hoverables::Alpha => {
+					let level = (ALPHA_BAR_HALF_LENGTH - local_pos.y) / (2.0 * ALPHA_BAR_HALF_LENGTH);
+					let level = level.clamp(0.0, 1.0);
+					self.set_alpha(MIN_ALPHA as f32 + level * (0xff - MIN_ALPHA) as f32);
 				},
 				_ => (),
 			}
-			return true;
+			if mouse_state == MouseClickState::Released {
+				self.grabbing = None;
+				self.scale_start = None;
+			}
+			return MouseResponse::Consumed;
 		}
 
 		if local_pos.x < -width / 2.0 || local_pos.x > width / 2.0 ||
 			local_pos.y < -height / 2.0 || local_pos.y > height / 2.0 {
 			self.controls_visible = false;
-			return false;
+			return MouseResponse::Ignored;
+		} else if self.locked {
+			// Only the padlock is live; anywhere else the input goes to whatever is underneath
+			self.controls_visible = self.over_lock(local_pos, width, height);
+			if !self.controls_visible {
+				self.hovering = None;
+				return MouseResponse::Ignored;
+			}
+			self.hovering = Some(Hoverables::Lock);
+			return if mouse_state == MouseClickState::Pressed { MouseResponse::ToggleLock } else { MouseResponse::Consumed };
 		} else {
 			self.controls_visible = true;
 		}
 
 		{
-			if 		local_pos.x > -85.0 &&
-					local_pos.x < 85.0 &&
-					local_pos.y > -height / 2.0 + 15.0 / self.transform.scale &&
-					local_pos.y < -height / 2.0 + 70.0 + 15.0 / self.transform.scale {
+			if self.over_rotate(local_pos, height) {
 				self.hovering = Some(Hoverables::Rotate);
-				match mouse_state {
-					MouseClickState::Pressed => {
-						self.grabbing = Some(Hoverables::Rotate);
-					},
-					_ => (),
+				if mouse_state == MouseClickState::Pressed {
+					self.start_grab(Hoverables::Rotate);
+					self.rotate_offset = cursor_angle(pos - self.transform.position) - self.transform.rotation;
 				}
 			} else if 	local_pos.x > -65.0 &&
 						local_pos.x < 65.0 &&
 						local_pos.y > -65.0 &&
 						local_pos.y < 65.0 {
 				self.hovering = Some(Hoverables::Translate);
-				match mouse_state {
-					MouseClickState::Pressed => {
-						self.grabbing = Some(Hoverables::Translate);
-					},
-					_ => (),
+				if mouse_state == MouseClickState::Pressed {
+					self.start_grab(Hoverables::Translate);
 				}
-			} else if 	local_pos.x > width / 2.0 - 85.0 - 15.0 / self.transform.scale &&
-						local_pos.x < width / 2.0 - 0.0 - 15.0 / self.transform.scale &&
-						local_pos.y > height / 2.0 - 85.0 - 15.0 / self.transform.scale &&
-						local_pos.y < height / 2.0 - 0.0 - 15.0 / self.transform.scale {
+			} else if 	local_pos.x > width / 2.0 - 85.0 - 15.0 / self.transform.scale.x.abs() &&
+						local_pos.x < width / 2.0 - 0.0 - 15.0 / self.transform.scale.x.abs() &&
+						local_pos.y > height / 2.0 - 85.0 - 15.0 / self.transform.scale.y.abs() &&
+						local_pos.y < height / 2.0 - 0.0 - 15.0 / self.transform.scale.y.abs() {
 				self.hovering = Some(Hoverables::Scale);
-				match mouse_state {
-					MouseClickState::Pressed => {
-						self.grabbing = Some(Hoverables::Scale);
-						self.scale_start = Some(pos);
-					},
-					_ => (),
+				if mouse_state == MouseClickState::Pressed {
+					self.start_grab(Hoverables::Scale);
+					self.scale_start = Some((pos, self.transform.scale));
+				}
+			} else if 	local_pos.x > width / 2.0 - 40.0 - 15.0 / self.transform.scale.x.abs() &&
+						local_pos.x < width / 2.0 - 0.0 - 15.0 / self.transform.scale.x.abs() &&
+						local_pos.y > -45.0 &&
+						local_pos.y < 45.0 {
+				self.hovering = Some(Hoverables::ScaleX);
+				if mouse_state == MouseClickState::Pressed {
+					self.start_grab(Hoverables::ScaleX);
 				}
-			} else if 	local_pos.x > -width / 2.0 + 15.0 / self.transform.scale &&
-						local_pos.x < -width / 2.0 + 50.0 + 15.0 / self.transform.scale &&
-						local_pos.y > -height / 2.0 + 15.0 / self.transform.scale &&
-						local_pos.y < -height / 2.0 + 50.0 + 15.0 / self.transform.scale {
+			} else if 	local_pos.x > -45.0 &&
+						local_pos.x < 45.0 &&
+						local_pos.y > height / 2.0 - 40.0 - 15.0 / self.transform.scale.y.abs() &&
+						local_pos.y < height / 2.0 - 0.0 - 15.0 / self.transform.scale.y.abs() {
+				self.hovering = Some(Hoverables::ScaleY);
+				if mouse_state == MouseClickState::Pressed {
+					self.start_grab(Hoverables::ScaleY);
+				}
+			} else if 	local_pos.x > -width / 2.0 + 15.0 / self.transform.scale.x.abs() &&
+						local_pos.x < -width / 2.0 + 50.0 + 15.0 / self.transform.scale.x.abs() &&
+						local_pos.y > -height / 2.0 + 15.0 / self.transform.scale.y.abs() &&
+						local_pos.y < -height / 2.0 + 50.0 + 15.0 / self.transform.scale.y.abs() {
 				self.hovering = Some(Hoverables::Delete);
-				match mouse_state {
-					MouseClickState::Pressed => {
-						self.dead = true;
-					},
-					_ => (),
+				if mouse_state == MouseClickState::Pressed {
+					self.dead = true;
+				}
+			} else if self.over_lock(local_pos, width, height) {
+				self.hovering = Some(Hoverables::Lock);
+				if mouse_state == MouseClickState::Pressed {
+					return MouseResponse::ToggleLock;
+				}
+			} else if 	local_pos.x > -width / 2.0 + 15.0 / self.transform.scale.x.abs() &&
+						local_pos.x < -width / 2.0 + ALPHA_BAR_WIDTH + 15.0 / self.transform.scale.x.abs() &&
+						local_pos.y > -ALPHA_BAR_HALF_LENGTH &&
+						local_pos.y < ALPHA_BAR_HALF_LENGTH {
+				self.hovering = Some(Hoverables::Alpha);
+				if mouse_state == MouseClickState::Pressed {
+					self.start_grab(Hoverables::Alpha);
+				}
+			} else if 	local_pos.x > -width / 2.0 + 65.0 + 15.0 / self.transform.scale.x.abs() &&
+						local_pos.x < -width / 2.0 + 115.0 + 15.0 / self.transform.scale.x.abs() &&
+						local_pos.y > -height / 2.0 + 15.0 / self.transform.scale.y.abs() &&
+						local_pos.y < -height / 2.0 + 65.0 + 15.0 / self.transform.scale.y.abs() {
+				self.hovering = Some(Hoverables::Duplicate);
+				if mouse_state == MouseClickState::Pressed {
+					// Held only to show the press, like the other handles
+					self.start_grab(Hoverables::Duplicate);
+					return MouseResponse::Duplicate;
+				}
+			} else if 	local_pos.x > width / 2.0 - 65.0 - 15.0 / self.transform.scale.x.abs() &&
+						local_pos.x < width / 2.0 - 15.0 - 15.0 / self.transform.scale.x.abs() &&
+						local_pos.y > -height / 2.0 + 15.0 / self.transform.scale.y.abs() &&
+						local_pos.y < -height / 2.0 + 65.0 + 15.0 / self.transform.scale.y.abs() {
+				self.hovering = Some(Hoverables::Tint);
+				if mouse_state == MouseClickState::Pressed {
+					return MouseResponse::CycleTint;
 				}
 			} else {
 				self.hovering = None;
 			}
 		}
-		true
+		MouseResponse::Consumed
+	}
+}
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SIZE: u32 = 1000;
+	const CENTER: (f32, f32) = (500.0, 500.0);
+
+	/// Press, optionally drag, and release the left button, all through `World::update`.
+	fn click(world: &mut World, path: &[(f32, f32)]) {
+		world.update(Some(path[0]), MouseClickState::Pressed, false);
+		for &pos in &path[1..] {
+			world.update(Some(pos), MouseClickState::Held, false);
+		}
+		world.update(path.last().copied(), MouseClickState::Released, false);
+	}
+
+	#[test]
+	fn click_without_moving_records_nothing() {
+		let mut world = World::new(SIZE, SIZE);
+		world.take_dirty();
+		click(&mut world, &[CENTER]);
+		assert!(world.history.undo.is_empty());
+		assert!(!world.take_dirty());
+	}
+
+	#[test]
+	fn drag_records_one_edit_at_release() {
+		let mut world = World::new(SIZE, SIZE);
+		let before = world.snapshot();
+		world.update(Some(CENTER), MouseClickState::Pressed, false);
+		world.update(Some((540.0, 500.0)), MouseClickState::Held, false);
+		assert!(world.history.undo.is_empty());
+		world.update(Some((580.0, 500.0)), MouseClickState::Released, false);
+		assert_eq!(world.history.undo, vec![before]);
+	}
+
+	#[test]
+	fn undo_keeps_locks_with_their_transforms() {
+		let mut world = World::new(SIZE, SIZE);
+		world.transforms[0].locked = true;
+		world.add_transform(pixel::Transform {
+			position: pixel::Vec2::new(500.0, 150.0),
+			scale: pixel::Vec2::new(0.2, 0.2),
+			..pixel::Transform::identity()
+		});
+		// Dragging the unlocked one raises it in front of the locked one
+		click(&mut world, &[(500.0, 150.0), (500.0, 180.0)]);
+		assert_eq!(world.transforms[0].id, 1);
+		world.undo();
+		let locked: Vec<(u64, bool)> = world.transforms.iter().map(|t| (t.id, t.locked)).collect();
+		assert_eq!(locked, vec![(0, true), (1, false)]);
+	}
+
+	#[test]
+	fn undo_keeps_disabled_transforms_disabled() {
+		let mut world = World::new(SIZE, SIZE);
+		world.transforms[0].enabled = false;
+		click(&mut world, &[CENTER, (540.0, 500.0)]);
+		world.undo();
+		assert!(!world.transforms[0].enabled);
+		assert!(world.rendered_transforms().is_empty());
+	}
+
+	#[test]
+	fn debug_cycle_solos_each_transform_then_wraps() {
+		let mut cycle = CycleState::new(Duration::from_secs(2));
+		assert!(!cycle.advance(Duration::from_millis(1500), 2));
+		assert!(cycle.is_enabled(0, 2) && !cycle.is_enabled(1, 2));
+		assert!(cycle.advance(Duration::from_millis(500), 2));
+		assert!(!cycle.is_enabled(0, 2) && cycle.is_enabled(1, 2));
+		assert!(cycle.advance(Duration::from_secs(2), 2));
+		assert!(cycle.is_enabled(0, 2) && cycle.is_enabled(1, 2));
+		assert!(cycle.advance(Duration::from_secs(2), 2));
+		assert_eq!(cycle.step, 0);
+	}
+
+	#[test]
+	fn animation_leaves_out_transforms_disabled_in_the_nearer_keyframe() {
+		let mut world = World::new(SIZE, SIZE);
+		world.add_transform(pixel::Transform::identity());
+		let from = world.snapshot();
+		world.transforms[1].enabled = false;
+		let to = world.snapshot();
+		assert_eq!(keyframe_transforms(&from, &to, 0.25).len(), 2);
+		assert_eq!(keyframe_transforms(&from, &to, 0.75), vec![from[0].transform]);
+	}
+
+	#[test]
+	fn scroll_burst_records_one_edit() {
+		let mut world = World::new(SIZE, SIZE);
+		world.update(Some(CENTER), MouseClickState::Idle, false);
+		let before = world.snapshot();
+		for _ in 0..30 {
+			assert!(world.scroll_hovered(0.1, false));
+		}
+		assert_eq!(world.history.undo, vec![before]);
+		// Moving off the transform and back starts a new burst
+		world.update(Some((5.0, 995.0)), MouseClickState::Idle, false);
+		world.update(Some(CENTER), MouseClickState::Idle, false);
+		assert!(world.scroll_hovered(0.1, false));
+		assert_eq!(world.history.undo.len(), 2);
+	}
+
+	#[test]
+	fn duplicate_handle_shows_the_press_until_release() {
+		let mut world = World::new(SIZE, SIZE);
+		let handle = (289.0, 254.0);
+		world.update(Some(handle), MouseClickState::Pressed, false);
+		assert_eq!(world.transforms.len(), 2);
+		assert!(world.transforms.iter().any(|t| t.grabbing == Some(Hoverables::Duplicate)));
+		world.update(Some(handle), MouseClickState::Released, false);
+		assert!(world.transforms.iter().all(|t| t.grabbing.is_none()));
+		assert_eq!(world.history.undo.len(), 1);
+	}
+
+	#[test]
+	fn present_reuses_its_scratch_buffer() {
+		let mut world = World::new(SIZE, SIZE);
+		let mut frame = vec![0u8; SIZE as usize * SIZE as usize * 4];
+		world.present(&mut frame, EditMode::View, &pixel::Transform::identity());
+		assert!(world.view_scratch.is_empty());
+		let zoomed = pixel::Transform { scale: pixel::Vec2::new(2.0, 2.0), ..pixel::Transform::identity() };
+		world.present(&mut frame, EditMode::View, &zoomed);
+		let scratch = world.view_scratch.as_ptr();
+		world.present(&mut frame, EditMode::View, &zoomed);
+		assert_eq!(world.view_scratch.as_ptr(), scratch);
+	}
+
+	/// Grab at `handle`, drag, release off the window and come back in elsewhere.
+	fn drag_out_of_window(world: &mut World, handle: (f32, f32)) -> pixel::Transform {
+		world.update(Some(handle), MouseClickState::Pressed, false);
+		world.update(Some((handle.0 + 40.0, handle.1 + 20.0)), MouseClickState::Held, false);
+		world.update(None, MouseClickState::Released, false);
+		let released = world.transforms[0].transform;
+		world.update(Some((100.0, 900.0)), MouseClickState::Idle, false);
+		world.update(Some((150.0, 950.0)), MouseClickState::Idle, false);
+		assert_eq!(world.transforms[0].transform, released, "transform still follows the cursor");
+		assert!(world.transforms[0].grabbing.is_none());
+		assert!(world.transforms[0].scale_start.is_none());
+		released
+	}
+
+	#[test]
+	fn translate_grab_ends_on_release_off_the_window() {
+		let mut world = World::new(SIZE, SIZE);
+		let released = drag_out_of_window(&mut world, CENTER);
+		assert_ne!(released.position, pixel::Vec2::new(CENTER.0, CENTER.1));
+	}
+
+	#[test]
+	fn scale_grab_ends_on_release_off_the_window() {
+		let mut world = World::new(SIZE, SIZE);
+		let released = drag_out_of_window(&mut world, (733.0, 733.0));
+		assert_ne!(released.scale, pixel::Vec2::new(T_SCALE, T_SCALE));
+	}
+
+	#[test]
+	fn missed_release_reads_as_idle() {
+		assert_eq!(MouseClickState::from_buttons(false, false, false, false), MouseClickState::Idle);
+		assert_eq!(MouseClickState::from_buttons(false, false, false, true), MouseClickState::Held);
+		assert_eq!(MouseClickState::from_buttons(true, false, false, true), MouseClickState::Idle);
+		let mut world = World::new(SIZE, SIZE);
+		world.update(Some(CENTER), MouseClickState::Pressed, false);
+		world.update(Some((540.0, 500.0)), MouseClickState::Held, false);
+		world.update(Some((580.0, 500.0)), MouseClickState::from_buttons(false, false, false, false), false);
+		assert!(world.transforms[0].grabbing.is_none());
+	}
+
+	fn assert_angle(actual: f32, expected: f32) {
+		assert!(normalize_angle(actual - expected).abs() < 1e-4, "{} != {}", actual, expected);
+	}
+
+	#[test]
+	fn cursor_angle_is_clockwise_from_up_in_every_quadrant() {
+		use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+		let cases = [
+			((0.0, -1.0), 0.0),
+			((1.0, -1.0), FRAC_PI_4),
+			((1.0, 0.0), FRAC_PI_2),
+			((1.0, 1.0), 3.0 * FRAC_PI_4),
+			((0.0, 1.0), PI),
+			((-1.0, 1.0), -3.0 * FRAC_PI_4),
+			((-1.0, 0.0), -FRAC_PI_2),
+			((-1.0, -1.0), -FRAC_PI_4),
+		];
+		for ((x, y), angle) in cases {
+			assert_angle(cursor_angle(pixel::Vec2::new(x, y)), angle);
+		}
+	}
+
+	#[test]
+	fn cursor_angle_is_continuous_across_y_zero() {
+		for x in [1.0, -1.0, 250.0] {
+			let above = cursor_angle(pixel::Vec2::new(x, -1e-4));
+			let below = cursor_angle(pixel::Vec2::new(x, 1e-4));
+			assert!(normalize_angle(above - below).abs() < 1e-3, "{} jumps from {} to {}", x, above, below);
+		}
+		// Straight down is atan2's branch cut, which normalizing the difference hides
+		let left = cursor_angle(pixel::Vec2::new(-1e-4, 1.0));
+		let right = cursor_angle(pixel::Vec2::new(1e-4, 1.0));
+		assert!(normalize_angle(left - right).abs() < 1e-3);
+	}
+
+	#[test]
+	fn normalize_angle_wraps_into_half_open_range() {
+		use std::f32::consts::{PI, TAU};
+		assert_angle(normalize_angle(3.0 * PI), PI);
+		assert!(normalize_angle(-PI) > 0.0);
+		assert!((normalize_angle(TAU + 0.5) - 0.5).abs() < 1e-5);
+		assert!((normalize_angle(-TAU - 0.5) + 0.5).abs() < 1e-5);
+		assert_eq!(normalize_angle(0.25), 0.25);
+	}
+
+	#[test]
+	fn rotate_drag_across_the_bottom_never_jumps() {
+		let mut world = World::new(SIZE, SIZE);
+		// The rotate handle, straight above the center
+		let at = |degrees: f32| {
+			let angle = degrees.to_radians();
+			(CENTER.0 + 240.0 * angle.sin(), CENTER.1 - 240.0 * angle.cos())
+		};
+		world.update(Some(at(0.0)), MouseClickState::Pressed, false);
+		assert_eq!(world.transforms[0].transform.rotation, 0.0, "pressing moved the transform");
+		let mut last = 0.0;
+		for step in 1..=20 {
+			world.update(Some(at(step as f32 * 10.0)), MouseClickState::Held, false);
+			let rotation = world.transforms[0].transform.rotation;
+			assert!(rotation > -std::f32::consts::PI && rotation <= std::f32::consts::PI);
+			assert!(normalize_angle(rotation - last).abs() < 0.2, "jumped from {} to {}", last, rotation);
+			last = rotation;
+		}
+		assert_angle(last, 200f32.to_radians());
+	}
+
+	/// Three small transforms in a row along the top, left to right and front to back.
+	fn three_in_a_row() -> World {
+		let mut world = World::new(SIZE, SIZE);
+		world.transforms.clear();
+		for x in [200.0, 500.0, 800.0] {
+			world.add_transform(pixel::Transform {
+				position: pixel::Vec2::new(x, 200.0),
+				scale: pixel::Vec2::new(0.2, 0.2),
+				..pixel::Transform::identity()
+			});
+		}
+		world
 	}
-}
\ No newline at end of file
+
+	/// Click the delete cross on the transform at `i`.
+	fn click_delete(world: &mut World, i: usize) {
+		let transform = &world.transforms[i].transform;
+		let corner = -(SIZE as f32) / 2.0 + 15.0 / transform.scale.x + 25.0;
+		let point = transform.apply(pixel::Vec2::new(corner, corner));
+		click(world, &[(point.x, point.y)]);
+	}
+
+	fn positions(world: &World) -> Vec<f32> {
+		world.transforms.iter().map(|t| t.transform.position.x).collect()
+	}
+
+	#[test]
+	fn deleting_a_group_removes_every_member_in_one_frame() {
+		let mut world = three_in_a_row();
+		world.transforms[0].selected = true;
+		world.transforms[2].selected = true;
+		click_delete(&mut world, 2);
+		assert_eq!(positions(&world), vec![500.0]);
+		world.undo();
+		assert_eq!(positions(&world), vec![200.0, 500.0, 800.0]);
+	}
+
+	#[test]
+	fn deleting_the_last_transform() {
+		let mut world = three_in_a_row();
+		click_delete(&mut world, 2);
+		assert_eq!(positions(&world), vec![200.0, 500.0]);
+		click_delete(&mut world, 1);
+		click_delete(&mut world, 0);
+		assert!(world.transforms.is_empty());
+	}
+
+	#[test]
+	fn deleting_a_transform_that_raise_on_click_moves_to_the_front() {
+		let mut world = three_in_a_row();
+		assert!(world.raise_on_click);
+		click_delete(&mut world, 1);
+		assert_eq!(positions(&world), vec![200.0, 800.0]);
+		// Hovering another transform during the delete doesn't get in the way either
+		world.update(Some((200.0, 200.0)), MouseClickState::Idle, false);
+		click_delete(&mut world, 1);
+		assert_eq!(positions(&world), vec![200.0]);
+	}
+
+	#[test]
+	fn click_on_a_transform_over_the_add_button_grabs_it_instead() {
+		let mut world = World::new(SIZE, SIZE);
+		world.transforms[0].transform.position = pixel::Vec2::new(50.0, 50.0);
+		world.update(Some((50.0, 50.0)), MouseClickState::Pressed, false);
+		assert_eq!(world.transforms.len(), 1);
+		assert!(world.transforms[0].grabbing == Some(Hoverables::Translate));
+		world.update(Some((60.0, 50.0)), MouseClickState::Released, false);
+		assert_eq!(world.transforms.len(), 1);
+		// With the transform out of the way the button works
+		world.transforms[0].transform.position = pixel::Vec2::new(500.0, 500.0);
+		click(&mut world, &[(50.0, 50.0)]);
+		assert_eq!(world.transforms.len(), 2);
+	}
+
+	#[test]
+	fn rotate_arc_stays_circular_on_a_wide_canvas() {
+		let (width, height) = (1600, 900);
+		let mut world = World::new(width, height);
+		world.transforms[0].controls_visible = true;
+		world.transforms[0].hovering = Some(Hoverables::Rotate);
+		let mut frame = vec![0u8; width as usize * height as usize * 4];
+		world.present(&mut frame, EditMode::Edit, &pixel::Transform::identity());
+		// The hovered arc's fully covered pixels come out in exactly the hovering color, and so do
+		// its arrowheads where writes stack up; those sit more than 45° off straight up
+		let transform = world.transforms[0].transform;
+		let center = transform.apply(pixel::Vec2::new(0.0, -(height as f32) / 2.0 + 100.0 + 15.0 / transform.scale.y));
+		let offsets: Vec<pixel::Vec2> = frame.chunks_exact(4)
+			.enumerate()
+			.filter(|(_, pixel)| pixel[..3] == HOVERING_COLOR)
+			.map(|(i, _)| pixel::Vec2::new((i % width as usize) as f32 + 0.5, (i / width as usize) as f32 + 0.5) - center)
+			.filter(|offset| offset.x.abs() < -offset.y * 0.7)
+			.collect();
+		assert!(offsets.len() > 200, "{} arc pixels", offsets.len());
+		for offset in &offsets {
+			let r = offset.magnitude();
+			assert!(r >= 75.0 * transform.scale.y - 1.0 && r <= 100.0 * transform.scale.y + 1.0, "{:?} is {} from the center", offset, r);
+		}
+		let balance = offsets.iter().map(|offset| offset.x).sum::<f32>() / offsets.len() as f32;
+		assert!(balance.abs() < 0.5, "arc leans {} pixels to one side", balance);
+	}
+
+	#[test]
+	fn rotate_arc_is_drawn_where_it_is_hit() {
+		let mut world = World::new(SIZE, SIZE);
+		// Squashed so the two axes disagree by a factor of two
+		world.transforms[0].transform.scale = pixel::Vec2::new(0.6, 0.3);
+		world.transforms[0].controls_visible = true;
+		world.transforms[0].hovering = Some(Hoverables::Rotate);
+		let mut frame = vec![0u8; SIZE as usize * SIZE as usize * 4];
+		world.present(&mut frame, EditMode::Edit, &pixel::Transform::identity());
+		let transform = &world.transforms[0];
+		let handle: Vec<pixel::Vec2> = frame.chunks_exact(4)
+			.enumerate()
+			.filter(|(_, pixel)| pixel[..3] == HOVERING_COLOR)
+			.map(|(i, _)| transform.transform.apply_inverse(pixel::Vec2::new((i % SIZE as usize) as f32 + 0.5, (i / SIZE as usize) as f32 + 0.5)))
+			.collect();
+		assert!(handle.len() > 200, "{} handle pixels", handle.len());
+		for local_pos in handle {
+			assert!(transform.over_rotate(local_pos, SIZE as f32), "{:?} is drawn but can't be grabbed", local_pos);
+		}
+	}
+
+	#[test]
+	fn growth_delta_of_a_converged_frame_is_background() {
+		let size = 300;
+		let mut world = World::new(size, size);
+		let seed = fractal::seed_triangle(size, size, world.palette.seed, world.palette.background);
+		let mut frame = vec![0u8; seed.len()];
+		let mut last_frame = vec![0u8; seed.len()];
+		let identity = pixel::Transform::identity();
+		let converged = (0..200).any(|_| world.draw(&seed, &mut frame, &mut last_frame, EditMode::View, ViewKind::Accumulated, &identity) == 0);
+		assert!(converged);
+		assert!(frame.chunks_exact(4).any(|pixel| pixel[..3] != world.palette.background));
+		let changed = world.draw(&seed, &mut frame, &mut last_frame, EditMode::View, ViewKind::GrowthDelta, &identity);
+		assert_eq!(changed, 0);
+		assert!(frame.chunks_exact(4).all(|pixel| pixel[..3] == world.palette.background));
+	}
+
+	#[test]
+	fn continuing_from_an_image_builds_on_it() {
+		let size = 300;
+		let mut world = World::new(size, size);
+		// Untinted, so copies keep the square's color
+		world.transforms[0].transform.tint = None;
+		let seed = fractal::seed_triangle(size, size, world.palette.seed, world.palette.background);
+		// A red square in the top left corner, saved at half size so loading has to scale it up
+		let red = [0xD0, 0x10, 0x10];
+		let mut image = vec![0u8; 150 * 150 * 4];
+		for (i, pixel) in image.chunks_exact_mut(4).enumerate() {
+			let inside = (5..20).contains(&(i % 150)) && (5..20).contains(&(i / 150));
+			pixel.copy_from_slice(&if inside { [red[0], red[1], red[2], 0xff] } else { [0, 0, 0, 0] });
+		}
+		let path = std::env::temp_dir().join(format!("rtfractal-continue-test-{}.png", std::process::id()));
+		image_io::save_canvas_png(&path, &image, 150, 150).unwrap();
+		let loaded = image_io::load_canvas_image(&path, size, size, world.palette.background);
+		std::fs::remove_file(&path).unwrap();
+		let mut continued = loaded.unwrap();
+		let first_frame = |world: &mut World, last_frame: &mut Vec<u8>| {
+			let mut frame = vec![0u8; seed.len()];
+			world.draw(&seed, &mut frame, last_frame, EditMode::View, ViewKind::Accumulated, &pixel::Transform::identity());
+			frame
+		};
+		// The transform shrinks the square towards the center: 25 maps to 150 + 0.54 * (25 - 150)
+		let copy = (82 * size as usize + 82) * 4;
+		let frame = first_frame(&mut world, &mut continued);
+		assert!(frame[copy..copy + 3].iter().zip(red).all(|(&c, r)| (c as i32 - r as i32).abs() < 0x20), "{:?}", &frame[copy..copy + 3]);
+		let frame = first_frame(&mut world, &mut vec![0u8; seed.len()]);
+		assert_eq!(frame[copy..copy + 3], world.palette.background);
+	}
+
+	#[test]
+	fn contraction_readout_shows_the_ratio_and_flags_expansion() {
+		let scaled = |x: f32, y: f32| pixel::Transform { scale: pixel::Vec2::new(x, y), rotation: 0.7, ..pixel::Transform::identity() };
+		assert_eq!(contraction_readout(&scaled(0.5, 0.5)), ("contraction 0.50".to_string(), &CONTRACTIVE_COLOR));
+		assert_eq!(contraction_readout(&scaled(1.2, 1.2)), ("contraction 1.20".to_string(), &EXPANSIVE_COLOR));
+		// The larger axis decides, flipped or not
+		assert_eq!(contraction_readout(&scaled(0.3, -1.2)), ("contraction 1.20".to_string(), &EXPANSIVE_COLOR));
+	}
+
+	#[test]
+	fn converged_loop_waits_until_input_wakes_it() {
+		let mut idle = IdleTracker::default();
+		idle.record(IDLE_PIXEL_EPSILON + 1);
+		assert_eq!(idle.control_flow(false), ControlFlow::Poll);
+		for _ in 1..IDLE_FRAMES {
+			idle.record(IDLE_PIXEL_EPSILON);
+		}
+		assert_eq!(idle.control_flow(false), ControlFlow::Poll);
+		idle.record(0);
+		assert_eq!(idle.control_flow(false), ControlFlow::Wait);
+		// Debug cycling changes the image on its own, so it keeps polling
+		assert_eq!(idle.control_flow(true), ControlFlow::Poll);
+
+		// Input resumes evolution, and it takes a full run of converged frames to idle again
+		idle.wake();
+		assert_eq!(idle.control_flow(false), ControlFlow::Poll);
+		idle.record(0);
+		assert_eq!(idle.control_flow(false), ControlFlow::Poll);
+	}
+
+	#[test]
+	fn one_busy_frame_restarts_the_convergence_count() {
+		let mut idle = IdleTracker::default();
+		for _ in 0..IDLE_FRAMES - 1 {
+			idle.record(0);
+		}
+		idle.record(IDLE_PIXEL_EPSILON + 1);
+		idle.record(0);
+		assert!(!idle.is_idle());
+	}
+
+	#[test]
+	fn saved_world_renders_the_same_image_after_loading() {
+		let mut world = World::new(SIZE, SIZE);
+		world.add_transform(pixel::Transform {
+			position: pixel::Vec2::new(300.0, 650.0),
+			rotation: 0.7,
+			scale: pixel::Vec2::new(-0.4, 0.3),
+			alpha: 180,
+			tint: Some([40, 200, 90]),
+			..pixel::Transform::identity()
+		});
+		world.add_transform(pixel::Transform {
+			position: pixel::Vec2::new(720.0, 380.0),
+			scale: pixel::Vec2::new(0.35, 0.35),
+			..pixel::Transform::identity()
+		});
+		world.transforms[2].enabled = false;
+		let path = std::env::temp_dir().join(format!("rtfractal-save-test-{}.json", std::process::id()));
+		world.to_saved().save(&path).unwrap();
+		let loaded = save::SavedWorld::load(&path);
+		std::fs::remove_file(&path).unwrap();
+		let mut reloaded = World::new(SIZE, SIZE);
+		reloaded.load_saved(loaded.unwrap());
+
+		let clear_buffer = seed_buffer(SIZE, SIZE, None, world.palette).unwrap();
+		let render = |world: &World| world.render_still(&clear_buffer, &world.rendered_transforms(), 8, 1);
+		assert_eq!(reloaded.rendered_transforms(), world.rendered_transforms());
+		assert!(render(&reloaded) == render(&world));
+	}
+
+	#[test]
+	fn library_label_names_the_picked_bookmark() {
+		let mut library = library::Library::default();
+		assert_eq!(library_label(&library, 0), None);
+		library.bookmark(&pixel::Transform::identity());
+		library.bookmark(&pixel::Transform::identity());
+		assert_eq!(library_label(&library, 1).as_deref(), Some("library 2/2: bookmark 2"));
+	}
+
+	#[test]
+	fn page_up_on_the_listed_entry_moves_it_forward() {
+		let mut world = three_in_a_row();
+		world.show_list = true;
+		world.step_list(1);
+		world.step_list(1);
+		world.step_list(1);
+		let lines = |world: &World| world.list_lines().into_iter().map(|(text, color)| (text, color == &SELECTED_COLOR)).collect::<Vec<_>>();
+		assert_eq!(lines(&world), vec![
+			("1 at 200 200".to_string(), false),
+			("2 at 500 200".to_string(), false),
+			("3 at 800 200".to_string(), true),
+		]);
+		// The cursor is nowhere near a transform, so the reorder goes to the listed entry
+		world.reorder_active(|i, _| i.saturating_sub(1));
+		assert_eq!(world.transforms.iter().map(|t| t.id).collect::<Vec<_>>(), vec![1, 3, 2]);
+		assert_eq!(lines(&world), vec![
+			("1 at 200 200".to_string(), false),
+			("2 at 800 200".to_string(), true),
+			("3 at 500 200".to_string(), false),
+		]);
+		world.undo();
+		assert_eq!(world.transforms.iter().map(|t| t.id).collect::<Vec<_>>(), vec![1, 2, 3]);
+		// Without the panel the keys only ever reorder the transform under the cursor
+		world.show_list = false;
+		world.reorder_active(|i, _| i.saturating_sub(1));
+		assert_eq!(world.transforms.iter().map(|t| t.id).collect::<Vec<_>>(), vec![1, 2, 3]);
+	}
+
+	#[test]
+	fn list_marks_hidden_and_locked_transforms() {
+		let mut world = three_in_a_row();
+		world.transforms[0].enabled = false;
+		world.transforms[2].locked = true;
+		world.step_list(-1);
+		let lines: Vec<String> = world.list_lines().into_iter().map(|(text, _)| text).collect();
+		assert_eq!(lines, ["1 at 200 200 hidden", "2 at 500 200", "3 at 800 200 locked"]);
+		assert!(world.transforms[0].selected);
+	}
+
+	#[test]
+	fn composition_read_from_a_reader_loads_like_the_file() {
+		let mut world = World::new(SIZE, SIZE);
+		world.add_transform(pixel::Transform {
+			position: pixel::Vec2::new(250.0, 640.0),
+			rotation: -0.4,
+			scale: pixel::Vec2::new(0.3, -0.5),
+			tint: Some([0xF2, 0xC1, 0x2E]),
+			..pixel::Transform::identity()
+		});
+		world.transforms[0].locked = true;
+		let path = std::env::temp_dir().join(format!("rtfractal-reader-test-{}.json", std::process::id()));
+		world.to_saved().save(&path).unwrap();
+		let contents = std::fs::read(&path).unwrap();
+		let from_file = save::SavedWorld::load(&path);
+		std::fs::remove_file(&path).unwrap();
+
+		let load = |saved: save::SavedWorld| {
+			let mut world = World::new(SIZE, SIZE);
+			world.load_saved(saved);
+			world.snapshot()
+		};
+		let from_reader = load(save::SavedWorld::from_reader(std::io::Cursor::new(contents)).unwrap());
+		assert_eq!(from_reader, load(from_file.unwrap()));
+		assert_eq!(from_reader.iter().map(|state| state.transform).collect::<Vec<_>>(), world.snapshot().iter().map(|state| state.transform).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn same_color_seed_rolls_the_same_palette() {
+		let rolled = |seed| {
+			let mut world = three_in_a_row();
+			world.color_rng = StdRng::seed_from_u64(seed);
+			world.roll_colors(true);
+			world
+		};
+		let tints = |world: &World| world.transforms.iter().map(|t| (t.id, t.transform.tint.unwrap())).collect::<Vec<_>>();
+		let (a, b) = (rolled(42), rolled(42));
+		assert_eq!(tints(&a), tints(&b));
+		assert_ne!(tints(&a), tints(&rolled(43)));
+		// Distinct transforms get distinct colors
+		let colors = tints(&a);
+		assert!(colors[0].1 != colors[1].1 && colors[1].1 != colors[2].1);
+
+		// The color stays with the transform's seed through a reorder and a save and load
+		let mut world = a;
+		world.show_list = true;
+		world.step_list(2);
+		world.reorder_active(|_, _| 0);
+		let mut reloaded = World::new(SIZE, SIZE);
+		reloaded.load_saved(world.to_saved());
+		let by_seed = |world: &World| {
+			let mut colors: Vec<(u64, [u8; 3])> = world.transforms.iter().map(|t| (t.color_seed.unwrap(), t.transform.tint.unwrap())).collect();
+			colors.sort();
+			colors
+		};
+		assert_eq!(by_seed(&reloaded), by_seed(&rolled(42)));
+		assert!(by_seed(&reloaded).iter().all(|&(seed, tint)| seed_tint(seed) == tint));
+	}
+
+	#[test]
+	fn picking_a_tint_by_hand_drops_the_seed() {
+		let mut world = three_in_a_row();
+		let spawned = world.snapshot();
+		world.roll_colors(true);
+		world.cycle_tint(1);
+		assert_eq!(world.transforms[1].color_seed, None);
+		assert_eq!(world.transforms[1].transform.tint, Some(TINT_PALETTE[0]));
+		// Rolling is an edit of its own
+		world.undo();
+		assert_eq!(world.snapshot(), spawned);
+		world.redo();
+		assert_eq!(world.transforms.iter().map(|t| t.color_seed.is_some()).collect::<Vec<_>>(), [true, false, true]);
+	}
+
+	#[test]
+	fn spawned_transforms_are_colored_from_the_color_seed() {
+		let spawned = |seed| {
+			let mut world = World::new(SIZE, SIZE);
+			world.reseed_colors(seed);
+			world.add_transform(pixel::Transform::identity());
+			world.duplicate(1);
+			world.transforms.iter().map(|t| (t.color_seed.unwrap(), t.transform.tint.unwrap())).collect::<Vec<_>>()
+		};
+		let colors = spawned(42);
+		assert_eq!(colors, spawned(42));
+		assert_ne!(colors, spawned(43));
+		assert!(colors.iter().all(|&(seed, tint)| seed_tint(seed) == tint));
+		// The duplicate in front of the added transform shares its color
+		assert!(colors[1] == colors[2] && colors[0] != colors[1]);
+		let mut world = World::new(SIZE, SIZE);
+		world.cycle_tint(0);
+		world.duplicate(0);
+		assert_eq!(world.transforms[0].transform.tint, Some(TINT_PALETTE[0]));
+		assert_eq!(world.transforms[0].color_seed, None);
+
+		// A transform that comes with a tint keeps it
+		let mut world = World::new(SIZE, SIZE);
+		world.add_transform(pixel::Transform { tint: Some(TINT_PALETTE[2]), ..pixel::Transform::identity() });
+		assert_eq!(world.transforms[1].color_seed, None);
+		assert_eq!(world.transforms[1].transform.tint, Some(TINT_PALETTE[2]));
+	}
+
+	#[test]
+	fn keys_over_a_padlock_only_unlock() {
+		let mut world = three_in_a_row();
+		world.transforms[1].locked = true;
+		// Hovering the padlock is the one way a locked transform shows its controls
+		world.transforms[1].controls_visible = true;
+		let before = world.snapshot();
+		let history = world.history.undo.len();
+		world.toggle_enabled_active();
+		world.flip_active(true);
+		world.flip_active(false);
+		world.toggle_alpha_normalization();
+		world.roll_colors(false);
+		world.reorder_active(|_, _| 0);
+		world.reorder_active(|_, count| count - 1);
+		assert_eq!(world.snapshot(), before);
+		assert_eq!(world.history.undo.len(), history);
+		world.toggle_lock_active();
+		assert!(!world.transforms[1].locked);
+	}
+
+	#[test]
+	fn list_steps_over_locked_transforms() {
+		let mut world = three_in_a_row();
+		world.transforms[1].locked = true;
+		world.show_list = true;
+		world.step_list(1);
+		world.step_list(1);
+		assert_eq!(world.transforms.iter().map(|t| t.selected).collect::<Vec<_>>(), [false, false, true]);
+		world.step_list(-1);
+		assert!(world.transforms[0].selected && !world.transforms[1].selected);
+
+		// Even selected some other way, keyboard edits leave a locked transform alone
+		let locked = world.transforms[1].state();
+		world.clear_selection();
+		world.transforms[1].selected = true;
+		let history = world.history.undo.len();
+		world.nudge_selected(Nudge::Translate(pixel::Vec2::new(10.0, 0.0)));
+		world.nudge_selected(Nudge::Rotate(0.2));
+		world.reorder_active(|_, _| 0);
+		world.delete_selected();
+		assert_eq!(world.transforms.len(), 3);
+		assert_eq!(world.transforms[1].state(), locked);
+		assert_eq!(world.history.undo.len(), history);
+	}
+
+	#[test]
+	fn debug_cycle_solos_only_enabled_transforms() {
+		let mut world = three_in_a_row();
+		world.transforms[1].enabled = false;
+		world.toggle_debug_cycle();
+		let positions = |world: &World| world.rendered_transforms().iter().map(|t| t.position.x).collect::<Vec<_>>();
+		let mut steps = vec![positions(&world)];
+		for _ in 0..3 {
+			world.advance_debug_cycle(DEBUG_CYCLE_INTERVAL);
+			steps.push(positions(&world));
+		}
+		// The first, then the third, then both, and round again; never an empty step
+		assert_eq!(steps, [vec![200.0], vec![800.0], vec![200.0, 800.0], vec![200.0]]);
+	}
+
+	#[test]
+	fn alpha_normalization_toggle_is_undoable() {
+		let mut world = World::new(SIZE, SIZE);
+		world.transforms[0].controls_visible = true;
+		world.take_dirty();
+		world.toggle_alpha_normalization();
+		assert!(world.transforms[0].transform.normalize_alpha_by_coverage);
+		assert!(world.take_dirty());
+		world.undo();
+		assert!(!world.transforms[0].transform.normalize_alpha_by_coverage);
+		world.redo();
+		assert!(world.transforms[0].transform.normalize_alpha_by_coverage);
+	}
+}
@@ -1,54 +1,434 @@
+/// An RGBA pixel buffer, or a rectangle within one.
+pub struct PixelGrid<'a> {
+	buffer: &'a mut [u8],
+	width: u32,
+	height: u32,
+	/// Bytes from the start of one row to the start of the next.
+	stride: usize,
+}
 
+impl<'a> PixelGrid<'a> {
+	/// Wrap `buffer` as a `width`×`height` grid whose rows start `stride` bytes apart.
+	///
+	/// A sub-rectangle of a larger image can be addressed by passing the slice starting at its
+	/// top-left pixel along with the parent's stride.
+	pub fn new(buffer: &'a mut [u8], width: u32, height: u32, stride: usize) -> Self {
+		assert!(stride >= width as usize * 4, "stride {} is narrower than {} pixels", stride, width);
+		let required = if height == 0 { 0 } else { (height as usize - 1) * stride + width as usize * 4 };
+		assert!(buffer.len() >= required, "buffer of {} bytes is too small for {}x{} with stride {}", buffer.len(), width, height, stride);
+		Self { buffer, width, height, stride }
+	}
 
-use crate::{WIDTH, HEIGHT};
+	pub fn width(&self) -> u32 {
+		self.width
+	}
 
-pub struct PixelGrid<'a>(pub &'a mut [u8]);
+	pub fn height(&self) -> u32 {
+		self.height
+	}
 
-impl<'a> PixelGrid<'a> {
+	fn index(&self, x: u32, y: u32) -> usize {
+		y as usize * self.stride + x as usize * 4
+	}
+
+	/// The pixel containing `point`, or `None` when it's off the grid or not finite.
+	fn pixel_at(&self, point: Vec2) -> Option<(u32, u32)> {
+		if !(point.x >= 0.0 && point.x < self.width as f32 && point.y >= 0.0 && point.y < self.height as f32) { return None }
+		Some((point.x as u32, point.y as u32))
+	}
+
+	/// Overwrite the pixel containing `point`; points off the grid are dropped.
 	pub fn set_pixel(&mut self, point: Vec2, pixel: &[u8; 3]) {
-		let x = point.x.clamp(0.0, (WIDTH - 1) as f32) as u32;
-		let y = point.y.clamp(0.0, (HEIGHT - 1) as f32) as u32;
-		let i = (x as usize + (y * WIDTH) as usize) * 4;
-		self.0[i..i + 3].copy_from_slice(pixel);
+		if let Some((x, y)) = self.pixel_at(point) {
+			self.set_pixel_unchecked(x, y, pixel);
+		}
 	}
 
+	/// Overwrite the pixel at `x`, `y`, which the caller has made sure is on the grid.
+	pub fn set_pixel_unchecked(&mut self, x: u32, y: u32, pixel: &[u8; 3]) {
+		debug_assert!(x < self.width && y < self.height, "pixel {},{} is off the {}x{} grid", x, y, self.width, self.height);
+		let i = self.index(x, y);
+		self.buffer[i..i + 3].copy_from_slice(pixel);
+	}
+
+	/// Blend `pixel` in at `point` mapped through `transform`; points landing off the grid are dropped.
 	pub fn set_pixel_transformed(&mut self, point: Vec2, transform: &Transform, pixel: &[u8; 3]) {
-		let point = transform.apply(point);
-		let x = point.x.clamp(0.0, (WIDTH - 1) as f32) as u32;
-		let y = point.y.clamp(0.0, (HEIGHT - 1) as f32) as u32;
-		let i = (x as usize + (y * WIDTH) as usize) * 4;
-		let alpha = transform.alpha as f32 / 255.0;
-		self.0[i + 0] = (((self.0[i + 0] as f32 / 255.0) * (1.0 - alpha) + (pixel[0] as f32 / 255.0) * alpha) * 255.0) as u8;
-		self.0[i + 1] = (((self.0[i + 1] as f32 / 255.0) * (1.0 - alpha) + (pixel[1] as f32 / 255.0) * alpha) * 255.0) as u8;
-		self.0[i + 2] = (((self.0[i + 2] as f32 / 255.0) * (1.0 - alpha) + (pixel[2] as f32 / 255.0) * alpha) * 255.0) as u8;
+		if let Some((x, y)) = self.pixel_at(transform.apply(point)) {
+			self.blend_pixel(x, y, pixel, transform.effective_alpha());
+		}
+	}
+
+	/// The RGBA pixel containing `point`, or `None` when it's off the grid.
+	pub fn sample(&self, point: Vec2) -> Option<[u8; 4]> {
+		let (x, y) = self.pixel_at(point)?;
+		let i = self.index(x, y);
+		Some([self.buffer[i], self.buffer[i + 1], self.buffer[i + 2], self.buffer[i + 3]])
+	}
+
+	/// Like `sample`, but interpolates between the four pixel centers around `point`.
+	///
+	/// Colors are weighted by alpha so transparent neighbours don't bleed their color into the
+	/// edge. Neighbours past the edge repeat the edge pixel.
+	pub fn sample_bilinear(&self, point: Vec2) -> Option<[u8; 4]> {
+		if !(point.x >= 0.0 && point.x < self.width as f32 && point.y >= 0.0 && point.y < self.height as f32) { return None }
+		// Pixel centers sit at half-integer coordinates
+		let gx = point.x - 0.5;
+		let gy = point.y - 0.5;
+		let x0 = gx.floor();
+		let y0 = gy.floor();
+		let tx = gx - x0;
+		let ty = gy - y0;
+		let mut color = [0.0f32; 3];
+		let mut alpha = 0.0;
+		for (dx, dy, weight) in [	(0.0, 0.0, (1.0 - tx) * (1.0 - ty)),
+									(1.0, 0.0, tx * (1.0 - ty)),
+									(0.0, 1.0, (1.0 - tx) * ty),
+									(1.0, 1.0, tx * ty)] {
+			let x = (x0 + dx).clamp(0.0, (self.width - 1) as f32) as u32;
+			let y = (y0 + dy).clamp(0.0, (self.height - 1) as f32) as u32;
+			let i = self.index(x, y);
+			let weight = weight * self.buffer[i + 3] as f32;
+			for (c, channel) in color.iter_mut().enumerate() {
+				*channel += self.buffer[i + c] as f32 * weight;
+			}
+			alpha += weight;
+		}
+		if alpha <= 0.0 { return Some([0; 4]) }
+		let [r, g, b] = color.map(|c| (c / alpha).round() as u8);
+		Some([r, g, b, alpha.round() as u8])
+	}
+
+	/// Fill the ring segment between radii `r_inner` and `r_outer` swept from angle `a0` to `a1`
+	/// (radians, screen space with y down).
+	///
+	/// Coverage comes from the signed distance of each pixel center to the ring's edges, so with
+	/// `aa` the boundary is graded over about one pixel instead of stepping.
+	#[allow(clippy::too_many_arguments)]
+	pub fn fill_arc(&mut self, center: Vec2, r_inner: f32, r_outer: f32, a0: f32, a1: f32, pixel: &[u8; 3], aa: bool) {
+		let mid = (a0 + a1) / 2.0;
+		let half_span = (a1 - a0).abs() / 2.0;
+		let min_x = (center.x - r_outer - 1.0).floor().max(0.0) as u32;
+		let max_x = (center.x + r_outer + 1.0).ceil().clamp(0.0, self.width as f32) as u32;
+		let min_y = (center.y - r_outer - 1.0).floor().max(0.0) as u32;
+		let max_y = (center.y + r_outer + 1.0).ceil().clamp(0.0, self.height as f32) as u32;
+		for y in min_y..max_y {
+			for x in min_x..max_x {
+				let offset = Vec2::new(x as f32 + 0.5, y as f32 + 0.5) - center;
+				let r = offset.magnitude();
+				let angle = offset.y.atan2(offset.x) - mid;
+				let angle = (angle + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+				// Distances inside each edge, positive when inside; the angular one is an arc length
+				let inside = (r - r_inner)
+					.min(r_outer - r)
+					.min((half_span - angle.abs()) * r);
+				let coverage =
+					if aa { (inside + 0.5).clamp(0.0, 1.0) }
+					else if inside >= 0.0 { 1.0 }
+					else { 0.0 };
+				if coverage <= 0.0 { continue }
+				self.blend_pixel(x, y, pixel, coverage);
+			}
+		}
+	}
+
+	/// Mix `pixel` into the pixel at `x`, `y` with weight `alpha` in 0..1.
+	pub fn blend_pixel(&mut self, x: u32, y: u32, pixel: &[u8; 3], alpha: f32) {
+		let i = self.index(x, y);
+		let alpha = quantize_alpha(alpha);
+		for (dst, &src) in self.buffer[i..i + 3].iter_mut().zip(pixel) {
+			*dst = blend_channel(*dst, src, alpha);
+		}
+	}
+
+	/// Lay `pixel` over the pixel at `x`, `y` with weight `alpha` in 0..1, raising its alpha channel
+	/// by the same amount.
+	pub fn composite_pixel(&mut self, x: u32, y: u32, pixel: &[u8; 3], alpha: f32) {
+		self.blend_pixel(x, y, pixel, alpha);
+		let i = self.index(x, y) + 3;
+		self.buffer[i] = blend_channel(self.buffer[i], 0xff, quantize_alpha(alpha));
+	}
+
+	/// Write `text` with its top-left corner at `pos` in the built-in bitmap font, screen aligned.
+	///
+	/// Letters show in upper case; characters the font lacks are left blank.
+	pub fn draw_text(&mut self, pos: Vec2, text: &str, pixel: &[u8; 3]) {
+		for (n, c) in text.chars().enumerate() {
+			let origin = pos + Vec2::new((n as u32 * GLYPH_ADVANCE * GLYPH_SCALE) as f32, 0.0);
+			for (row, bits) in glyph(c).iter().enumerate() {
+				for column in 0..GLYPH_WIDTH {
+					if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 { continue }
+					for dy in 0..GLYPH_SCALE {
+						for dx in 0..GLYPH_SCALE {
+							let offset = Vec2::new((column * GLYPH_SCALE + dx) as f32, (row as u32 * GLYPH_SCALE + dy) as f32);
+							self.set_pixel(origin + offset, pixel);
+						}
+					}
+				}
+			}
+		}
+	}
+
+	/// Make the grid symmetric about its center column by averaging each pixel with its mirror.
+	pub fn fold_vertical(&mut self) {
+		for y in 0..self.height {
+			for x in 0..self.width / 2 {
+				self.fold_pair(x, y, self.width - 1 - x, y);
+			}
+		}
+	}
+
+	/// Make the grid symmetric about its center row by averaging each pixel with its mirror.
+	pub fn fold_horizontal(&mut self) {
+		for y in 0..self.height / 2 {
+			for x in 0..self.width {
+				self.fold_pair(x, y, x, self.height - 1 - y);
+			}
+		}
+	}
+
+	fn fold_pair(&mut self, x0: u32, y0: u32, x1: u32, y1: u32) {
+		let a = self.index(x0, y0);
+		let b = self.index(x1, y1);
+		for c in 0..4 {
+			let mean = ((self.buffer[a + c] as u16 + self.buffer[b + c] as u16) / 2) as u8;
+			self.buffer[a + c] = mean;
+			self.buffer[b + c] = mean;
+		}
 	}
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Transform {
 	pub position: Vec2,
 	pub rotation: f32,
-	pub scale: f32,
+	/// Independent x and y scale, applied before rotation.
+	pub scale: Vec2,
 	pub alpha: u8,
+	pub normalize_alpha_by_coverage: bool,
+	/// Color that copies made by this transform are pulled towards.
+	pub tint: Option<[u8; 3]>,
+	/// Share of the attractor in 0..=1: scales the copy's opacity and, in the chaos game, how often
+	/// the transform is picked. At 0 the transform is left out entirely.
+	pub weight: f32,
 }
 
+/// How far each feedback generation pulls a copy's color towards its transform's tint.
+const TINT_STRENGTH: f32 = 0.35;
+
 impl Transform {
-	fn apply(&self, point: Vec2) -> Vec2 {
+	/// The transform that leaves every point where it is.
+	pub fn identity() -> Self {
+		Transform {
+			position: Vec2::new(0.0, 0.0),
+			rotation: 0.0,
+			scale: Vec2::new(1.0, 1.0),
+			alpha: 0xff,
+			normalize_alpha_by_coverage: false,
+			tint: None,
+			weight: 1.0,
+		}
+	}
+
+	pub fn is_identity(&self) -> bool {
+		self.position.x == 0.0 && self.position.y == 0.0 &&
+		self.rotation == 0.0 &&
+		self.scale.x == 1.0 && self.scale.y == 1.0
+	}
+
+	/// This transform followed by `view`, a viewport camera made of a uniform zoom and an offset.
+	///
+	/// Such a view commutes with rotation, so the result is again a single `Transform`.
+	pub fn viewed_through(&self, view: &Transform) -> Transform {
+		Transform {
+			position: view.apply(self.position),
+			scale: self.scale * view.scale.x,
+			..*self
+		}
+	}
+
+	/// Contraction ratio of the transform: the largest singular value of its linear part.
+	///
+	/// Rotation preserves lengths, so this is just the larger scale magnitude.
+	pub fn max_scale_factor(&self) -> f32 {
+		self.scale.x.abs().max(self.scale.y.abs())
+	}
+
+	/// Fraction of the canvas covered by the transformed copy.
+	pub fn coverage(&self) -> f32 {
+		(self.scale.x * self.scale.y).abs().min(1.0)
+	}
+
+	/// Per-write blend factor in 0..1.
+	///
+	/// A copy covering a fraction `c` of the canvas lands roughly `1 / c` source pixels on every
+	/// destination pixel, so with normalization on the alpha is chosen such that those stacked
+	/// writes add up to the base alpha, making small and large copies contribute equally.
+	pub fn effective_alpha(&self) -> f32 {
+		let alpha = self.alpha as f32 / 255.0;
+		if self.normalize_alpha_by_coverage {
+			1.0 - (1.0 - alpha).powf(self.coverage())
+		} else {
+			alpha
+		}
+	}
+
+	/// Blend from this transform (`t` = 0) to `other` (`t` = 1), turning the short way round.
+	///
+	/// Tints blend when both are set; otherwise the nearer keyframe's tint is used.
+	pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+		let turn = (other.rotation - self.rotation + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+		let tint = match (self.tint, other.tint) {
+			(Some(a), Some(b)) => Some([0, 1, 2].map(|c| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * t).round() as u8)),
+			(a, b) => if t < 0.5 { a } else { b },
+		};
+		Transform {
+			position: self.position + (other.position - self.position) * t,
+			rotation: self.rotation + turn * t,
+			scale: self.scale + (other.scale - self.scale) * t,
+			alpha: (self.alpha as f32 + (other.alpha as f32 - self.alpha as f32) * t).round() as u8,
+			normalize_alpha_by_coverage: if t < 0.5 { self.normalize_alpha_by_coverage } else { other.normalize_alpha_by_coverage },
+			tint,
+			weight: self.weight + (other.weight - self.weight) * t,
+		}
+	}
+
+	/// Screen-space bounding box of a `width`×`height` canvas after this transform, as (min, max)
+	/// corners.
+	pub fn canvas_bounds(&self, width: u32, height: u32) -> (Vec2, Vec2) {
+		let half_width = width as f32 / 2.0;
+		let half_height = height as f32 / 2.0;
+		let mut min = Vec2::new(f32::INFINITY, f32::INFINITY);
+		let mut max = Vec2::new(f32::NEG_INFINITY, f32::NEG_INFINITY);
+		for (x, y) in [(-half_width, -half_height), (half_width, -half_height), (-half_width, half_height), (half_width, half_height)] {
+			let corner = self.apply(Vec2::new(x, y));
+			min = Vec2::new(min.x.min(corner.x), min.y.min(corner.y));
+			max = Vec2::new(max.x.max(corner.x), max.y.max(corner.y));
+		}
+		(min, max)
+	}
+
+	/// Opacity of the whole copy when it's composited onto the canvas in one pass.
+	///
+	/// Forward-mapping a copy covering a fraction `c` of the canvas stacked about `1 / c` writes of
+	/// `effective_alpha` on every destination pixel, so this compounds it the same way; with
+	/// normalization on that comes back out to the base alpha.
+	pub fn copy_alpha(&self) -> f32 {
+		1.0 - (1.0 - self.effective_alpha()).powf(1.0 / self.coverage())
+	}
+
+	/// Mix `pixel` towards the tint, if any.
+	///
+	/// The result is a convex combination of the source color and the tint, so it always lies
+	/// between them: repeated through the feedback loop, a copy converges geometrically onto the
+	/// tint color, to within one level per channel once rounding stops it, instead of drifting to
+	/// white or black, and deeper copies are just more saturated.
+	pub fn tinted(&self, pixel: [u8; 3]) -> [u8; 3] {
+		let tint = match self.tint {
+			Some(tint) => tint,
+			None => return pixel,
+		};
+		let mix = |src: u8, tint: u8| (src as f32 + (tint as f32 - src as f32) * TINT_STRENGTH).round() as u8;
+		[mix(pixel[0], tint[0]), mix(pixel[1], tint[1]), mix(pixel[2], tint[2])]
+	}
+
+	/// The transform that applies `other` first and then this one.
+	///
+	/// The product is only a `Transform` again when this transform's scale has the same magnitude
+	/// on both axes or `other` is unrotated (or turned by half a turn); otherwise it needs a shear
+	/// and there is none. Alphas and weights multiply, and this transform's tint wins over `other`'s.
+	///
+	/// ```
+	/// use rtfractal::pixel::{Transform, Vec2};
+	///
+	/// let half = Transform { scale: Vec2::new(0.5, 0.5), ..Transform::identity() };
+	/// let shift = Transform { position: Vec2::new(10.0, 0.0), ..Transform::identity() };
+	/// let point = Vec2::new(4.0, 2.0);
+	/// assert_eq!(half.compose(&shift).unwrap().apply(point), half.apply(shift.apply(point)));
+	///
+	/// let squash = Transform { scale: Vec2::new(1.0, 0.5), ..Transform::identity() };
+	/// let turn = Transform { rotation: 0.5, ..Transform::identity() };
+	/// assert!(squash.compose(&turn).is_none());
+	/// ```
+	pub fn compose(&self, other: &Transform) -> Option<Transform> {
+		// diag(sx, sy) R(a) only equals R(b) diag(..) when the scale is uniform up to sign or R(a) = ±I
+		let uniform = self.scale.x.abs() == self.scale.y.abs();
+		if !uniform && other.rotation.sin().abs() > 1e-6 { return None }
+		// A mirrored linear part turns the other way: diag(1, -1) R(a) = R(-a) diag(1, -1)
+		let mirrored = uniform && self.scale.x * self.scale.y < 0.0;
+		Some(Transform {
+			position: self.apply(other.position),
+			rotation: self.rotation + if mirrored { -other.rotation } else { other.rotation },
+			scale: self.scale * other.scale,
+			alpha: ((self.alpha as u32 * other.alpha as u32 + 127) / 255) as u8,
+			normalize_alpha_by_coverage: self.normalize_alpha_by_coverage,
+			tint: self.tint.or(other.tint),
+			weight: self.weight * other.weight,
+		})
+	}
+
+	/// The opaque transform whose `apply` maps `p` to `[a b; c d] p + (e, f)`.
+	///
+	/// `None` unless the matrix is a rotation times an axis scale, possibly flipped: one with a
+	/// shear, or that collapses an axis, has no `Transform`.
+	///
+	/// ```
+	/// use rtfractal::pixel::{Transform, Vec2};
+	///
+	/// let turned = Transform::from_affine([0.0, -0.5, 0.5, 0.0, 10.0, 20.0]).unwrap();
+	/// assert_eq!(turned.apply(Vec2::new(2.0, 0.0)), Vec2::new(10.0, 21.0));
+	/// assert!(Transform::from_affine([1.0, 0.5, 0.0, 1.0, 0.0, 0.0]).is_none());
+	/// ```
+	pub fn from_affine([a, b, c, d, e, f]: [f32; 6]) -> Option<Transform> {
+		if ![a, b, c, d, e, f].iter().all(|v| v.is_finite()) { return None }
+		// R(r) diag(sx, sy) has columns sx (cos r, sin r) and sy (-sin r, cos r)
+		let scale_x = a.hypot(c);
+		let scale_y = (a * d - b * c) / scale_x;
+		if scale_x == 0.0 || scale_y == 0.0 || !scale_y.is_finite() { return None }
+		// Perpendicular columns, or there is a shear
+		if (a * b + c * d).abs() > 1e-5 * scale_x * b.hypot(d) { return None }
+		Some(Transform {
+			position: Vec2::new(e, f),
+			rotation: c.atan2(a),
+			scale: Vec2::new(scale_x, scale_y),
+			..Transform::identity()
+		})
+	}
+
+	pub fn apply(&self, point: Vec2) -> Vec2 {
 		let point = point * self.scale;
 		let point = point.rotate(self.rotation);
-		let point = point + self.position;
-		point
+		point + self.position
 	}
 
 	pub fn apply_inverse(&self, point: Vec2) -> Vec2 {
 		let point = point - self.position;
 		let point = point.rotate(-self.rotation);
-		let point = point / self.scale;
-		point
+		point / self.scale
+	}
+
+	/// The canvas point that this transform maps onto itself during feedback.
+	///
+	/// Feedback feeds canvas points in relative to the canvas center, so this solves
+	/// `(I - M)p = t - Mc` for the linear part `M`, position `t` and canvas center `c`.
+	/// Returns `None` when the system is singular (e.g. an unrotated transform at full scale).
+	pub fn fixed_point(&self, width: u32, height: u32) -> Option<Vec2> {
+		let center = Vec2::new(width as f32 / 2.0, height as f32 / 2.0);
+		let b = self.position - (center * self.scale).rotate(self.rotation);
+		let (sin, cos) = self.rotation.sin_cos();
+		// M = R * S, so (I - M) = [[a11, a12], [a21, a22]]
+		let a11 = 1.0 - cos * self.scale.x;
+		let a12 = sin * self.scale.y;
+		let a21 = -sin * self.scale.x;
+		let a22 = 1.0 - cos * self.scale.y;
+		let det = a11 * a22 - a12 * a21;
+		if det.abs() < f32::EPSILON { return None }
+		Some(Vec2::new(	(a22 * b.x - a12 * b.y) / det,
+						(a11 * b.y - a21 * b.x) / det))
 	}
 }
 
 // Vec2
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Vec2 {
 	pub x: f32,
 	pub y: f32,
@@ -59,7 +439,7 @@ impl Vec2 {
 		Self {x, y}
 	}
 
-	fn rotate(&self, angle: f32) -> Self {
+	pub fn rotate(&self, angle: f32) -> Self {
 		let sin = angle.sin();
 		let cos = angle.cos();
 		Self {
@@ -68,16 +448,17 @@ impl Vec2 {
 		}
 	}
 
-	fn normalized(&self) -> Self {
-		let magnitude = self.magnitude();
-		Self {
-			x: self.x / magnitude,
-			y: self.y / magnitude,
-		}
+	pub fn magnitude(&self) -> f32 {
+		self.dot(*self).sqrt()
 	}
 
-	pub fn magnitude(&self) -> f32 {
-		(self.x * self.x + self.y * self.y).sqrt()
+	/// ```
+	/// use rtfractal::pixel::Vec2;
+	///
+	/// assert_eq!(Vec2::new(1.0, 2.0).dot(Vec2::new(3.0, -1.0)), 1.0);
+	/// ```
+	pub fn dot(&self, other: Self) -> f32 {
+		self.x * other.x + self.y * other.y
 	}
 }
 
@@ -139,4 +520,323 @@ impl std::ops::Add for Vec2 {
 		let y = self.y + rhs.y;
 		Self::new(x, y)
 	}
-}
\ No newline at end of file
+}
+
+impl std::ops::AddAssign for Vec2 {
+	fn add_assign(&mut self, rhs: Self) {
+		*self = *self + rhs;
+	}
+}
+
+impl std::ops::SubAssign for Vec2 {
+	fn sub_assign(&mut self, rhs: Self) {
+		*self = *self - rhs;
+	}
+}
+
+impl std::ops::Neg for Vec2 {
+	type Output = Self;
+
+	fn neg(self) -> Self {
+		Self::new(-self.x, -self.y)
+	}
+}
+
+/// A 0..1 blend weight as the nearest of 0..=255.
+fn quantize_alpha(alpha: f32) -> u32 {
+	(alpha.clamp(0.0, 1.0) * 255.0).round() as u32
+}
+
+/// `dst` moved towards `src` by `alpha`/255, rounded to nearest.
+///
+/// Integer math with rounding rather than truncation, so blending a color onto itself gives it
+/// back exactly: feedback applies this thousands of times, and truncating would drift every
+/// channel down towards black.
+fn blend_channel(dst: u8, src: u8, alpha: u32) -> u8 {
+	((dst as u32 * (255 - alpha) + src as u32 * alpha + 127) / 255) as u8
+}
+
+const GLYPH_WIDTH: u32 = 5;
+/// Horizontal distance between the starts of consecutive glyphs, before scaling.
+const GLYPH_ADVANCE: u32 = 6;
+/// Screen pixels per font pixel.
+const GLYPH_SCALE: u32 = 2;
+
+/// On-screen height of a line drawn by `PixelGrid::draw_text`.
+pub const TEXT_HEIGHT: f32 = (7 * GLYPH_SCALE) as f32;
+
+/// On-screen width of `text` as drawn by `PixelGrid::draw_text`.
+pub fn text_width(text: &str) -> f32 {
+	(text.chars().count() as u32 * GLYPH_ADVANCE * GLYPH_SCALE) as f32
+}
+
+/// Rows of the 5x7 glyph for `c`, top first, with the leftmost column in bit 4.
+fn glyph(c: char) -> [u8; 7] {
+	match c.to_ascii_uppercase() {
+		'0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+		'1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+		'2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+		'3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+		'4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+		'5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+		'6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+		'7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+		'8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+		'9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+		'A' => [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+		'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+		'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+		'D' => [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100],
+		'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+		'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+		'G' => [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111],
+		'H' => [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+		'I' => [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+		'J' => [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100],
+		'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
+		'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+		'M' => [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001],
+		'N' => [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001],
+		'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+		'P' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000],
+		'Q' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101],
+		'R' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001],
+		'S' => [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110],
+		'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+		'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+		'V' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+		'W' => [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010],
+		'X' => [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001],
+		'Y' => [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100],
+		'Z' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111],
+		'.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
+		'-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
+		'%' => [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011],
+		'°' => [0b01100, 0b10010, 0b10010, 0b01100, 0b00000, 0b00000, 0b00000],
+		':' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000],
+		'/' => [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000],
+		_ => [0; 7],
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_close(a: Vec2, b: Vec2) {
+		assert!((a - b).magnitude() < 1e-3, "{:?} != {:?}", a, b);
+	}
+
+	/// Points spread over and past a 1000 pixel canvas.
+	fn points() -> impl Iterator<Item = Vec2> {
+		(-2..=2).flat_map(|x| (-2..=2).map(move |y| Vec2::new(x as f32 * 300.0 + 7.0, y as f32 * 250.0 - 3.0)))
+	}
+
+	#[test]
+	fn compose_matches_applying_in_turn() {
+		let pairs = [
+			// Non-uniform scale under an unrotated or half-turned transform
+			(Vec2::new(0.8, 0.3), 0.7, 0.0),
+			(Vec2::new(0.8, -0.3), -1.2, std::f32::consts::PI),
+			// Uniform scale, mirrored or not, under a rotated transform
+			(Vec2::new(0.5, 0.5), 0.4, 1.1),
+			(Vec2::new(-0.5, 0.5), 2.0, -0.6),
+		];
+		for (scale, rotation, other_rotation) in pairs {
+			let a = Transform { position: Vec2::new(30.0, -12.0), rotation, scale, ..Transform::identity() };
+			let b = Transform {
+				position: Vec2::new(-80.0, 45.0),
+				rotation: other_rotation,
+				scale: Vec2::new(0.6, -0.9),
+				..Transform::identity()
+			};
+			let ab = a.compose(&b).unwrap();
+			for point in points() {
+				assert_close(ab.apply(point), a.apply(b.apply(point)));
+			}
+		}
+	}
+
+	#[test]
+	fn compose_refuses_products_that_need_a_shear() {
+		let a = Transform { scale: Vec2::new(0.8, 0.3), rotation: 0.2, ..Transform::identity() };
+		let b = Transform { rotation: 0.9, scale: Vec2::new(0.5, 0.5), ..Transform::identity() };
+		assert!(a.compose(&b).is_none());
+		// The other way round the non-uniform scale is applied first, which is fine
+		let ba = b.compose(&a).unwrap();
+		for point in points() {
+			assert_close(ba.apply(point), b.apply(a.apply(point)));
+		}
+	}
+
+	#[test]
+	fn from_affine_matches_the_matrix() {
+		let matrices = [
+			[1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+			// Turned, squashed, and flipped
+			[0.4, -0.15, 0.3, 0.2, 12.0, -7.0],
+			[0.4, 0.15, 0.3, -0.2, -3.0, 5.0],
+			[-0.5, 0.0, 0.0, 0.25, 0.0, 100.0],
+		];
+		for [a, b, c, d, e, f] in matrices {
+			let transform = Transform::from_affine([a, b, c, d, e, f]).unwrap();
+			for point in points() {
+				assert_close(transform.apply(point), Vec2::new(a * point.x + b * point.y + e, c * point.x + d * point.y + f));
+			}
+		}
+	}
+
+	#[test]
+	fn from_affine_refuses_shears_and_collapsed_axes() {
+		assert!(Transform::from_affine([0.5, 0.2, 0.0, 0.5, 0.0, 0.0]).is_none());
+		assert!(Transform::from_affine([0.5, 1.0, 0.25, 0.5, 0.0, 0.0]).is_none());
+		assert!(Transform::from_affine([0.0, 0.5, 0.0, 0.5, 0.0, 0.0]).is_none());
+		assert!(Transform::from_affine([0.5, 0.0, 0.0, 0.5, f32::NAN, 0.0]).is_none());
+	}
+
+	#[test]
+	fn blending_a_color_onto_itself_never_drifts() {
+		for color in [[0x00, 0x01, 0x7F], [0x80, 0xFE, 0xFF], [0x23, 0xA9, 0x50], [0xE3, 0xE3, 0xE3]] {
+			for alpha in 0..=255 {
+				let mut buffer = [color[0], color[1], color[2], 0xff];
+				let mut grid = PixelGrid::new(&mut buffer, 1, 1, 4);
+				for _ in 0..1000 {
+					grid.blend_pixel(0, 0, &color, alpha as f32 / 255.0);
+				}
+				assert_eq!(buffer[..3], color, "alpha {}", alpha);
+			}
+		}
+	}
+
+	#[test]
+	fn full_alpha_replaces_and_zero_alpha_preserves() {
+		for dst in 0..=255 {
+			for src in 0..=255 {
+				assert_eq!(blend_channel(dst, src, 255), src);
+				assert_eq!(blend_channel(dst, src, 0), dst);
+			}
+		}
+		assert_eq!(quantize_alpha(1.0), 255);
+		assert_eq!(quantize_alpha(0.0), 0);
+		assert_eq!(quantize_alpha(-0.5), 0);
+		assert_eq!(quantize_alpha(1.5), 255);
+	}
+
+	#[test]
+	fn writes_off_the_grid_leave_the_buffer_untouched() {
+		let mut buffer = [0x55u8; 4 * 4 * 4];
+		let mut grid = PixelGrid::new(&mut buffer, 4, 4, 16);
+		let shift = Transform { position: Vec2::new(-10.0, 2.0), ..Transform::identity() };
+		for point in [
+			Vec2::new(-0.5, 1.0),
+			Vec2::new(1.0, -0.01),
+			Vec2::new(4.0, 1.0),
+			Vec2::new(1.0, 4.5),
+			Vec2::new(f32::NAN, 1.0),
+			Vec2::new(1.0, f32::NAN),
+			Vec2::new(f32::INFINITY, f32::NEG_INFINITY),
+		] {
+			grid.set_pixel(point, &[0xff, 0, 0]);
+			grid.set_pixel_transformed(point, &Transform::identity(), &[0xff, 0, 0]);
+			grid.set_pixel_transformed(point + Vec2::new(10.0, -2.0), &shift, &[0xff, 0, 0]);
+		}
+		assert!(buffer.iter().all(|&channel| channel == 0x55));
+	}
+
+	#[test]
+	fn apply_inverse_undoes_apply() {
+		for scale in [Vec2::new(0.5, 0.5), Vec2::new(0.8, 0.25), Vec2::new(-0.6, 0.4), Vec2::new(0.3, -0.9), Vec2::new(-0.7, -0.2)] {
+			for rotation in [0.0, 0.6, -2.5, std::f32::consts::PI] {
+				let transform = Transform { position: Vec2::new(320.0, -45.0), rotation, scale, ..Transform::identity() };
+				for point in points() {
+					assert_close(transform.apply_inverse(transform.apply(point)), point);
+					assert_close(transform.apply(transform.apply_inverse(point)), point);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn repeated_tinting_converges_on_the_tint() {
+		let tint = [0x2A, 0x9D, 0x8F];
+		let transform = Transform { tint: Some(tint), ..Transform::identity() };
+		let distance = |color: [u8; 3]| color.iter().zip(tint).map(|(&c, t)| (c as i32 - t as i32).abs()).max().unwrap_or(0);
+		for start in [[0x00, 0x00, 0x00], [0xff, 0xff, 0xff], [0xE6, 0x39, 0x46]] {
+			let mut color = start;
+			for _ in 0..64 {
+				let next = transform.tinted(color);
+				// Every pass moves closer, without overshooting into white or black
+				assert!(distance(next) <= distance(color), "{:?} moved away from {:?}", next, tint);
+				color = next;
+			}
+			// Rounding can leave it a level short, but then it stays put
+			assert!(distance(color) <= 1, "{:?} from {:?}", color, start);
+			assert_eq!(transform.tinted(color), color);
+		}
+		// The tint itself is a fixed point, and no tint leaves colors alone
+		assert_eq!(transform.tinted(tint), tint);
+		assert_eq!(Transform::identity().tinted([0x12, 0x34, 0x56]), [0x12, 0x34, 0x56]);
+	}
+
+	#[test]
+	fn fixed_point_of_a_centered_half_scale_is_the_center() {
+		let half = Transform { position: Vec2::new(500.0, 300.0), scale: Vec2::new(0.5, 0.5), ..Transform::identity() };
+		assert_close(half.fixed_point(1000, 600).unwrap(), Vec2::new(500.0, 300.0));
+		// Off center and rotated, the point still maps onto itself through feedback's recentering
+		let turned = Transform { position: Vec2::new(200.0, 150.0), rotation: 0.8, scale: Vec2::new(0.4, -0.6), ..Transform::identity() };
+		let point = turned.fixed_point(1000, 600).unwrap();
+		assert_close(turned.apply(point - Vec2::new(500.0, 300.0)), point);
+		// An unrotated full-scale transform only shifts, and has none
+		assert_eq!(Transform::identity().fixed_point(1000, 600), None);
+	}
+
+	/// A white full ring between radii 10 and 20 around (32, 32) on black, one channel per pixel.
+	fn ring(aa: bool) -> Vec<u8> {
+		let mut buffer = vec![0u8; 64 * 64 * 4];
+		let mut grid = PixelGrid::new(&mut buffer, 64, 64, 64 * 4);
+		grid.fill_arc(Vec2::new(32.0, 32.0), 10.0, 20.0, -std::f32::consts::PI, std::f32::consts::PI, &[0xff, 0xff, 0xff], aa);
+		buffer.chunks_exact(4).map(|pixel| pixel[0]).collect()
+	}
+
+	#[test]
+	fn anti_aliased_arc_grades_its_boundary() {
+		let radius = |i: usize| (Vec2::new((i % 64) as f32 + 0.5, (i / 64) as f32 + 0.5) - Vec2::new(32.0, 32.0)).magnitude();
+		let smooth = ring(true);
+		let graded: Vec<usize> = (0..smooth.len()).filter(|&i| smooth[i] > 0 && smooth[i] < 0xff).collect();
+		// Partial coverage all the way round both edges, and only there
+		assert!(graded.len() > 100, "{} graded pixels", graded.len());
+		for &i in &graded {
+			let r = radius(i);
+			assert!((r - 10.0).abs() < 1.0 || (r - 20.0).abs() < 1.0, "{} graded at radius {}", smooth[i], r);
+		}
+		// Falling off steadily across the outer edge
+		let row: Vec<u8> = (50..55).map(|x| smooth[32 * 64 + x]).collect();
+		assert!(row.windows(2).all(|pair| pair[0] >= pair[1]), "{:?}", row);
+		assert!(row.iter().any(|&v| v > 0 && v < 0xff), "{:?}", row);
+		// Without anti-aliasing every pixel is either in or out
+		assert!(ring(false).iter().all(|&v| v == 0 || v == 0xff));
+	}
+
+	#[test]
+	fn sub_rectangle_grid_writes_through_the_parent_stride() {
+		// A 3x2 grid over the 10x6 parent starting at pixel (4, 1)
+		let (parent_width, stride) = (10, 10 * 4);
+		let mut parent = vec![0u8; stride * 6];
+		let offset = stride + 4 * 4;
+		let mut grid = PixelGrid::new(&mut parent[offset..], 3, 2, stride);
+		grid.set_pixel(Vec2::new(0.5, 0.5), &[1, 1, 1]);
+		grid.set_pixel(Vec2::new(2.5, 1.5), &[2, 2, 2]);
+		// Past the grid's own edge, though still inside the parent
+		grid.set_pixel(Vec2::new(3.5, 0.5), &[9, 9, 9]);
+		assert_eq!(grid.sample(Vec2::new(2.5, 1.5)), Some([2, 2, 2, 0]));
+		let written: Vec<(usize, u8)> = parent.chunks_exact(4).enumerate().filter(|(_, p)| p[0] != 0).map(|(i, p)| (i, p[0])).collect();
+		assert_eq!(written, vec![(parent_width + 4, 1), (2 * parent_width + 6, 2)]);
+	}
+
+	#[test]
+	#[should_panic(expected = "too small")]
+	fn grid_rejects_a_buffer_too_short_for_its_stride() {
+		let mut buffer = [0u8; 10 * 4 * 2];
+		PixelGrid::new(&mut buffer[4..], 10, 2, 10 * 4);
+	}
+}
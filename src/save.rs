@@ -0,0 +1,154 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::pixel::{Transform, Vec2};
+use crate::{MIN_SCALE, MAX_SCALE};
+
+pub const SAVE_PATH: &str = "fractal.json";
+
+/// On-disk form of a composition.
+#[derive(Serialize, Deserialize)]
+pub struct SavedWorld {
+	pub transforms: Vec<SavedTransform>,
+	/// Width and height of the canvas the positions were laid out on; older files leave it out.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub canvas: Option<[u32; 2]>,
+}
+
+impl SavedWorld {
+	/// Read and validate the composition at `path`.
+	pub fn load(path: &Path) -> io::Result<Self> {
+		Self::from_reader(fs::File::open(path)?)
+	}
+
+	/// Read and validate a composition from `reader` until it ends, such as a pipe on stdin.
+	pub fn from_reader(mut reader: impl Read) -> io::Result<Self> {
+		let mut contents = String::new();
+		reader.read_to_string(&mut contents)?;
+		let saved: Self = serde_json::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+		let transforms = saved.transforms.into_iter()
+			.map(SavedTransform::validated)
+			.collect::<Result<_, _>>()
+			.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+		let canvas = saved.canvas.filter(|&[width, height]| width > 0 && height > 0);
+		Ok(Self { transforms, canvas })
+	}
+
+	pub fn save(&self, path: &Path) -> io::Result<()> {
+		let contents = serde_json::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+		fs::write(path, contents)
+	}
+}
+
+/// On-disk mirror of `pixel::Transform`.
+#[derive(Serialize, Deserialize, Copy, Clone)]
+pub struct SavedTransform {
+	pub x: f32,
+	pub y: f32,
+	pub rotation: f32,
+	/// Uniform scale, or the x scale when `scale_y` is present.
+	pub scale: f32,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub scale_y: Option<f32>,
+	pub alpha: u8,
+	#[serde(default)]
+	pub normalize_alpha_by_coverage: bool,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub tint: Option<[u8; 3]>,
+	/// Missing from older files, which predate weights.
+	#[serde(default = "full_weight")]
+	pub weight: f32,
+	/// Editor state rather than part of the transform: whether it contributes to the fractal.
+	#[serde(default = "enabled")]
+	pub enabled: bool,
+	/// Editor state rather than part of the transform: whether its handles are locked.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub locked: bool,
+	/// Editor state rather than part of the transform: the seed its tint was rolled from.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub color_seed: Option<u64>,
+}
+
+impl SavedTransform {
+	/// Reject non-finite values and clamp the scale into the range the scale handle allows, so a
+	/// hand-edited file can't produce a transform that can no longer be grabbed.
+	pub fn validated(mut self) -> Result<Self, String> {
+		let scale_y = self.scale_y.unwrap_or(self.scale);
+		if ![self.x, self.y, self.rotation, self.scale, scale_y, self.weight].iter().all(|v| v.is_finite()) {
+			return Err("transform has a non-finite position, rotation, scale or weight".to_string());
+		}
+		self.weight = self.weight.clamp(0.0, 1.0);
+		self.scale = clamp_scale(self.scale);
+		self.scale_y = self.scale_y.map(clamp_scale);
+		Ok(self)
+	}
+}
+
+fn full_weight() -> f32 {
+	1.0
+}
+
+fn enabled() -> bool {
+	true
+}
+
+/// Clamp the magnitude and keep the sign, which marks a flipped axis.
+fn clamp_scale(scale: f32) -> f32 {
+	if !(MIN_SCALE..=MAX_SCALE).contains(&scale.abs()) {
+		warn!("Clamping transform scale {} into ±{}..={}", scale, MIN_SCALE, MAX_SCALE);
+	}
+	crate::clamp_scale(scale)
+}
+
+impl From<&Transform> for SavedTransform {
+	fn from(transform: &Transform) -> Self {
+		Self {
+			x: transform.position.x,
+			y: transform.position.y,
+			rotation: transform.rotation,
+			scale: transform.scale.x,
+			scale_y: if transform.scale.y == transform.scale.x { None } else { Some(transform.scale.y) },
+			alpha: transform.alpha,
+			normalize_alpha_by_coverage: transform.normalize_alpha_by_coverage,
+			tint: transform.tint,
+			weight: transform.weight,
+			enabled: true,
+			locked: false,
+			color_seed: None,
+		}
+	}
+}
+
+impl From<SavedTransform> for Transform {
+	fn from(saved: SavedTransform) -> Self {
+		Self {
+			position: Vec2::new(saved.x, saved.y),
+			rotation: saved.rotation,
+			scale: Vec2::new(saved.scale, saved.scale_y.unwrap_or(saved.scale)),
+			alpha: saved.alpha,
+			normalize_alpha_by_coverage: saved.normalize_alpha_by_coverage,
+			tint: saved.tint,
+			weight: saved.weight,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn malformed_input_is_rejected_as_invalid_data() {
+		for input in ["", "{", "{\"transforms\": 3}", "[1, 2]"] {
+			let error = SavedWorld::from_reader(io::Cursor::new(input)).err().unwrap();
+			assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{:?}", input);
+		}
+		// Valid JSON, but a transform that can't be rendered
+		let infinite = r#"{"transforms": [{"x": 0, "y": 0, "rotation": 0, "scale": 1e40, "alpha": 255}]}"#;
+		assert_eq!(SavedWorld::from_reader(io::Cursor::new(infinite)).err().unwrap().kind(), io::ErrorKind::InvalidData);
+	}
+}
@@ -0,0 +1,66 @@
+use rtfractal::fractal::{seed_triangle, FractalWorld};
+use rtfractal::pixel::{Transform, Vec2};
+
+const SIZE: u32 = 256;
+const SEED: [u8; 3] = [0x23, 0xA9, 0x50];
+const BACKGROUND: [u8; 3] = [0xE3, 0xE3, 0xE3];
+
+/// Two half-size copies side by side, the bottom row of a Sierpinski triangle.
+fn sierpinski() -> FractalWorld {
+	let mut world = FractalWorld::new(SIZE, SIZE, seed_triangle(SIZE, SIZE, SEED, BACKGROUND));
+	for x in [64.0, 192.0] {
+		world.add_transform(Transform {
+			position: Vec2::new(x, 128.0),
+			scale: Vec2::new(0.5, 0.5),
+			..Transform::identity()
+		});
+	}
+	world
+}
+
+/// Pixels of the seed triangle itself.
+fn triangle_pixels() -> usize {
+	seed_triangle(SIZE, SIZE, SEED, BACKGROUND).chunks_exact(4).filter(|pixel| pixel[3] == 0xff).count()
+}
+
+/// Seed-colored pixels after growing `world` for `iterations` generations.
+fn seed_pixels(world: &FractalWorld, iterations: u32) -> usize {
+	let mut frame = vec![0; SIZE as usize * SIZE as usize * 4];
+	world.render_into(&mut frame, iterations);
+	assert!(frame.chunks_exact(4).all(|pixel| pixel[3] == 0xff));
+	frame.chunks_exact(4).filter(|pixel| pixel[..3] == SEED).count()
+}
+
+#[test]
+fn first_generation_is_the_seed() {
+	assert_eq!(seed_pixels(&sierpinski(), 1), triangle_pixels());
+}
+
+#[test]
+fn second_generation_adds_two_quarter_size_copies() {
+	let triangle = triangle_pixels();
+	let count = seed_pixels(&sierpinski(), 2);
+	// The copies overlap the seed, so they add something but never more than their own area
+	assert!(count > triangle, "{} seed pixels, seed alone has {}", count, triangle);
+	assert!(count <= triangle + 2 * (triangle / 4 + SIZE as usize), "{} seed pixels, seed alone has {}", count, triangle);
+}
+
+#[test]
+fn generations_converge_within_the_geometric_bound() {
+	let world = sierpinski();
+	let counts: Vec<usize> = (1..=12).map(|iterations| seed_pixels(&world, iterations)).collect();
+	assert!(counts.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", counts);
+	// Generation n adds at most 2^n copies of 4^-n the area: half of the previous generation's
+	assert!(counts[11] < 2 * triangle_pixels(), "{:?}", counts);
+	assert_eq!(counts[7], counts[11], "{:?}", counts);
+}
+
+#[test]
+fn removing_the_transforms_leaves_the_seed() {
+	let mut world = sierpinski();
+	world.remove_transform(1);
+	let one_copy = seed_pixels(&world, 8);
+	assert!(one_copy > triangle_pixels() && one_copy < seed_pixels(&sierpinski(), 8));
+	world.remove_transform(0);
+	assert_eq!(seed_pixels(&world, 8), triangle_pixels());
+}